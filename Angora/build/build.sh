@@ -21,6 +21,8 @@ rm -rf ${PREFIX}
 mkdir -p ${PREFIX}
 mkdir -p ${PREFIX}/lib
 cp target/release/fuzzer ${PREFIX}
+cp target/release/angora-cmin ${PREFIX}
+cp target/release/angora-tmin ${PREFIX}
 cp target/release/*.a ${PREFIX}/lib
 
 cd llvm_mode
@@ -0,0 +1,40 @@
+// The arguments and environment variables taken from the input,
+// written by fuzzer/src/executor/process.rs and read by runtime_fast/src/argv.rs
+// in each child of the fork server.
+use serde_derive::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgInput {
+    pub args: Vec<Vec<u8>>,
+    pub envs: Vec<(String, Vec<u8>)>,
+}
+
+impl ArgInput {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let data = bincode::serialize(self).map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    pub fn read(path: &Path) -> Option<Self> {
+        let data = fs::read(path).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arg_input() {
+        let input = ArgInput {
+            args: vec![b"-x".to_vec(), vec![], vec![0xff, b'\n']],
+            envs: vec![("LANG".to_string(), b"C".to_vec())],
+        };
+        let path = std::env::temp_dir().join(format!("angora_arg_input_{}", std::process::id()));
+        input.write(&path).unwrap();
+        assert_eq!(ArgInput::read(&path), Some(input));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -8,6 +8,9 @@ pub const ENABLE_MICRO_RANDOM_LEN: bool = true;
 pub const DISABLE_INFER_SHAPE_IF_HAS_AND_OP: bool = true;
 pub const PREFER_FAST_COND: bool = true;
 
+// qpriority.rs: executions spent on a condition count once per unit.
+pub const QUEUE_EXEC_UNIT: usize = 1000;
+
 // ************ Resources ****************
 pub const MAX_INPUT_LEN: usize = 15000;
 
@@ -27,6 +30,48 @@ pub const MAX_NUM_MINIMAL_OPTIMA_ALL: usize = 28;
 // based the bit bucket: [1], [2], [3], [4, 7], [8, 15], [16, 31], [32, 127], [128, infinity]
 pub const MAX_COND_ORDER: u32 = 16;
 
+// shm_input.rs: the max size of the input passed in shared memory,
+// the larger ones are only in the file.
+pub const SHM_INPUT_SIZE: usize = 1 << 20;
+
+// executor.rs: the output kept for each crash and hang, the last bytes of it.
+pub const OUTPUT_LOG_SIZE: usize = 64 << 10; // bytes
+// The crashes and hangs waiting to be re-run for their output,
+// and how long they can be re-run between two fuzzing rounds.
+pub const OUTPUT_QUEUE_SIZE: usize = 16;
+pub const OUTPUT_RERUN_TIME: u64 = 5; // seconds
+// process.rs: the output of every run with ANGORA_KEEP_OUTPUT, rotated beyond it.
+pub const KEEP_OUTPUT_SIZE: u64 = 64 << 20; // bytes
+
+// forksrv.rs: how long to wait for the fork server to be up,
+// e.g. the initialization before __ANGORA_INIT() in the program.
+pub const FORKSRV_INIT_TIMEOUT: u64 = 10; // seconds
+
+// net.rs: how long the program has to handle the input sent over the network
+// (after its response if it is waited for), before it is stopped.
+pub const NET_WAIT_TIME: u64 = 10; // ms
+
+// depot.rs: the inputs of the queue kept in memory.
+pub const INPUT_CACHE_SIZE: usize = 64; // MB, 0 for disabled
+
+// depot/target.rs: the distance of the inputs not known to reach any target.
+pub const TARGET_FAR_DIST: u32 = 64;
+
+// depot.rs: retry the unsolvable and timed-out conditions,
+// if reached from another input or after the interval.
+pub const REVIVE_MAX_TIMES: usize = 2;
+pub const REVIVE_INTERVAL: u64 = 30; // minutes, 0 for only when reached by other inputs
+
+// fuzz_main.rs
+pub const CHECKPOINT_INTERVAL: u64 = 10; // minutes, 0 for only on exit
+
+// tmin.rs
+pub const TMIN_BLOCK_STEPS: usize = 16;
+pub const TMIN_NORMAL_BYTE: u8 = b'0';
+
+// triage.rs
+pub const TRIAGE_TOP_FRAMES: usize = 3;
+
 // ************ Mutation ****************
 // SEARCH
 pub const ENABLE_DET_MUTATION: bool = true;
@@ -43,3 +88,10 @@ pub const MUTATE_ARITH_MAX: u32 = 30;
 pub const RANDOM_LEN_NUM: usize = 30;
 pub const MAX_HAVOC_FLIP_TIMES: usize = 45; // for all bytes
 pub const MAX_SPLICE_TIMES: usize = 45;
+pub const MAX_CUSTOM_MUTATOR_TIMES: usize = 45;
+// Taint-restricted havoc
+pub const FOCUS_HAVOC_OPEN_PROB: f64 = 0.8;
+pub const FOCUS_HAVOC_MAX_RETRY: usize = 4;
+// Adaptive scheduling of havoc operators
+pub const MUT_OP_PRIOR_EXEC: f64 = 100.0;
+pub const MUT_OP_MIN_PROB: f64 = 0.02;
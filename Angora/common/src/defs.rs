@@ -2,28 +2,74 @@ use std;
 // -- envs
 pub static DISABLE_CPU_BINDING_VAR: &str = "ANGORA_DISABLE_CPU_BINDING";
 pub static ANGORA_BIN_DIR: &str = "ANGORA_BIN_DIR";
+pub static KEEP_OUTPUT_VAR: &str = "ANGORA_KEEP_OUTPUT";
 
 // executor.rs
 pub static TRACK_OUTPUT_VAR: &str = "ANGORA_TRACK_OUTPUT";
 pub static COND_STMT_ENV_VAR: &str = "ANGORA_COND_STMT_SHM_ID";
 pub static BRANCHES_SHM_ENV_VAR: &str = "ANGORA_BRANCHES_SHM_ID";
+pub static SHM_INPUT_ENV_VAR: &str = "ANGORA_INPUT_SHM_ID";
 pub static LD_LIBRARY_PATH_VAR: &str = "LD_LIBRARY_PATH";
 pub static ASAN_OPTIONS_VAR: &str = "ASAN_OPTIONS";
 pub static MSAN_OPTIONS_VAR: &str = "MSAN_OPTIONS";
-pub static ASAN_OPTIONS_CONTENT: &str =
-    "abort_on_error=1:detect_leaks=0:symbolize=0:allocator_may_return_null=1";
+pub static UBSAN_OPTIONS_VAR: &str = "UBSAN_OPTIONS";
+pub static TSAN_OPTIONS_VAR: &str = "TSAN_OPTIONS";
+pub static LSAN_OPTIONS_VAR: &str = "LSAN_OPTIONS";
+// Each sanitizer exits with its own code instead of aborting, to tell which one finds a crash.
+pub const ASAN_ERROR_CODE: i32 = 85;
 pub const MSAN_ERROR_CODE: i32 = 86;
+pub const UBSAN_ERROR_CODE: i32 = 87;
+pub const TSAN_ERROR_CODE: i32 = 88;
+pub const LSAN_ERROR_CODE: i32 = 89;
+pub static ASAN_OPTIONS_CONTENT: &str =
+    "exitcode=85:abort_on_error=0:detect_leaks=0:symbolize=0:allocator_may_return_null=1";
 pub static MSAN_OPTIONS_CONTENT: &str =
-    "exit_code=86:symbolize=0:abort_on_error=1:allocator_may_return_null=1:msan_track_origins=0";
+    "exit_code=86:symbolize=0:abort_on_error=0:allocator_may_return_null=1:msan_track_origins=0";
+pub static UBSAN_OPTIONS_CONTENT: &str =
+    "exitcode=87:halt_on_error=1:abort_on_error=0:symbolize=0:print_stacktrace=0";
+pub static TSAN_OPTIONS_CONTENT: &str =
+    "exitcode=88:halt_on_error=1:abort_on_error=0:symbolize=0";
+pub static LSAN_OPTIONS_CONTENT: &str = "exitcode=89:abort_on_error=0:symbolize=0";
+// for crash triage
+pub static ASAN_OPTIONS_SYMBOLIZE_CONTENT: &str =
+    "exitcode=85:abort_on_error=0:detect_leaks=0:symbolize=1:allocator_may_return_null=1";
+pub static MSAN_OPTIONS_SYMBOLIZE_CONTENT: &str =
+    "exit_code=86:symbolize=1:abort_on_error=0:allocator_may_return_null=1:msan_track_origins=0";
+pub static UBSAN_OPTIONS_SYMBOLIZE_CONTENT: &str =
+    "exitcode=87:halt_on_error=1:abort_on_error=0:symbolize=1:print_stacktrace=1";
+pub static TSAN_OPTIONS_SYMBOLIZE_CONTENT: &str =
+    "exitcode=88:halt_on_error=1:abort_on_error=0:symbolize=1";
+pub static LSAN_OPTIONS_SYMBOLIZE_CONTENT: &str = "exitcode=89:abort_on_error=0:symbolize=1";
 
 // depot.rs
 pub static CRASHES_DIR: &str = "crashes";
 pub static HANGS_DIR: &str = "hangs";
 pub static INPUTS_DIR: &str = "queue";
+pub static TRIAGE_FILE: &str = "triage.json";
+pub static PROVENANCE_FILE: &str = "provenance.jsonl";
+// the output of a crash or hang, e.g. crashes/id:000001,...,op:gd.log
+pub static OUTPUT_LOG_SUFFIX: &str = ".log";
 
 // forksrv.rs
 pub static ENABLE_FORKSRV: &str = "ANGORA_ENABLE_FORKSRV";
 pub static FORKSRV_SOCKET_PATH_VAR: &str = "ANGORA_FORKSRV_SOCKET_PATH";
+// persistent mode, also in llvm_mode/include/defs.h
+pub static PERSIST_ENV_VAR: &str = "ANGORA_PERSISTENT";
+pub static PERSIST_SIG: &str = "##SIG_ANGORA_PERSISTENT##";
+// deferred fork server, also in llvm_mode/include/defs.h
+pub static DEFER_ENV_VAR: &str = "ANGORA_DEFER_FORKSRV";
+pub static DEFER_SIG: &str = "##SIG_ANGORA_DEFER_FORKSRV##";
+// network input, the port the program reads the input from,
+// also in llvm_mode/include/defs.h
+pub static NET_PORT_VAR: &str = "ANGORA_NET_PORT";
+// arguments and environment variables from the input: the file of them
+// for the fork server, and where they are in the input for the track program
+// (also in llvm_mode/include/defs.h)
+pub static ARG_INPUT_FILE_VAR: &str = "ANGORA_ARG_INPUT_FILE";
+// The offsets of the parts of a multi-file input (@@1, @@2, ..) in the test case
+pub static INPUT_PARTS_VAR: &str = "ANGORA_INPUT_PARTS";
+pub static TAINT_ARGS_VAR: &str = "ANGORA_TAINT_ARGS";
+pub static TAINT_ENVS_VAR: &str = "ANGORA_TAINT_ENVS";
 
 // command.rs
 pub static ANGORA_DIR_NAME: &str = "angora";
@@ -31,6 +77,14 @@ pub static ANGORA_LOG_FILE: &str = "angora.log";
 pub static ANGORA_DLOG_FILE: &str = "angora.dlog";
 pub static COND_QUEUE_FILE: &str = "cond_queue.csv";
 pub static CHART_STAT_FILE: &str = "chart_stat.json";
+pub static CHECKPOINT_FILE: &str = "checkpoint.bin";
+pub static CONFIG_FILE: &str = "config.toml";
+pub static SUMMARY_FILE: &str = "summary.json";
+
+// stop.rs: exit codes of the fuzzer
+pub const EXIT_CLEAN: i32 = 0;
+pub const EXIT_CRASH: i32 = 1;
+pub const EXIT_ERROR: i32 = 2;
 
 // tmpfs.rs
 pub static PERSIST_TRACK_FILES: &str = "ANGORA_DISABLE_TMPFS";
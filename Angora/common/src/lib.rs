@@ -1,8 +1,10 @@
+pub mod arg_input;
 pub mod cond_stmt_base;
 pub mod config;
 pub mod defs;
 pub mod log_data;
 pub mod shm;
+pub mod shm_input;
 pub mod tag;
 
 
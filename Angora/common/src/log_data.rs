@@ -2,11 +2,22 @@ use crate::{cond_stmt_base::CondStmtBase, tag::TagSeg};
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+// A value from the input (label `lb`) compared with the number of units read,
+// which is a candidate of length field.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct LenFieldLog {
+    pub lb: u32,
+    pub val: u64,
+    pub read_offset: u32,
+    pub read_unit: u32,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct LogData {
     pub cond_list: Vec<CondStmtBase>,
     pub tags: HashMap<u32, Vec<TagSeg>>,
     pub magic_bytes: HashMap<usize, (Vec<u8>, Vec<u8>)>,
+    pub len_fields: Vec<LenFieldLog>,
 }
 
 impl LogData {
@@ -15,6 +26,7 @@ impl LogData {
             cond_list: vec![],
             tags: HashMap::new(),
             magic_bytes: HashMap::new(),
+            len_fields: vec![],
         }
     }
 }
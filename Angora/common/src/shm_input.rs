@@ -0,0 +1,51 @@
+// The input passed in shared memory instead of the file,
+// written by fuzzer/src/executor/process.rs and read by runtime_fast/src/shm_input.rs.
+use crate::config::SHM_INPUT_SIZE;
+
+// The input is too large for the shared memory, read the file.
+pub const SHM_INPUT_IN_FILE: u32 = u32::MAX;
+
+#[repr(C)] // It should be repr C since we will used it in shared memory
+pub struct ShmInput {
+    pub len: u32,
+    pub buf: [u8; SHM_INPUT_SIZE],
+}
+
+impl ShmInput {
+    // Return false if it is too large, and the program should read the file.
+    pub fn set(&mut self, buf: &[u8]) -> bool {
+        if buf.len() > SHM_INPUT_SIZE {
+            self.len = SHM_INPUT_IN_FILE;
+            return false;
+        }
+        self.buf[..buf.len()].copy_from_slice(buf);
+        self.len = buf.len() as u32;
+        true
+    }
+
+    pub fn get(&self) -> Option<&[u8]> {
+        if self.len == SHM_INPUT_IN_FILE {
+            None
+        } else {
+            Some(&self.buf[..self.len as usize])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shm::SHM;
+
+    #[test]
+    fn test_shm_input() {
+        let mut input = SHM::<ShmInput>::new();
+        assert!(input.set(b"angora"));
+        let reader = SHM::<ShmInput>::from_id(input.get_id());
+        assert_eq!(reader.get(), Some(&b"angora"[..]));
+        assert!(input.set(b""));
+        assert_eq!(reader.get(), Some(&b""[..]));
+        assert!(!input.set(&vec![0; SHM_INPUT_SIZE + 1]));
+        assert_eq!(reader.get(), None);
+    }
+}
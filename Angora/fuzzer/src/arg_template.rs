@@ -0,0 +1,175 @@
+// Map slices of the input onto arguments and environment variables,
+// e.g. `-x {0:16} @@` passes bytes 0..16 of the input as the second argument.
+// The input is still written to the file (or stdin) as a whole.
+use angora_common::arg_input::ArgInput;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Lit(String),
+    // offset, length
+    Slice(usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgTemplate {
+    args: Vec<Vec<Piece>>,
+    envs: Vec<(String, Vec<Piece>)>,
+}
+
+// The arguments and envs of an input, and where the slices are
+// in them for the track program.
+pub struct RenderedArgs {
+    pub input: ArgInput,
+    // IDX:POS:OFFSET:LEN;..., IDX is the index in argv.
+    pub taint_args: String,
+    // NAME:POS:OFFSET:LEN;...
+    pub taint_envs: String,
+}
+
+fn parse_pieces(s: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    let mut rest = s;
+    while let Some(begin) = rest.find('{') {
+        if begin > 0 {
+            pieces.push(Piece::Lit(rest[..begin].to_string()));
+        }
+        let end = match rest[begin..].find('}') {
+            Some(e) => begin + e,
+            None => return Err(format!("Unclosed {{ in {}", s)),
+        };
+        let slice = &rest[begin + 1..end];
+        let mut it = slice.splitn(2, ':');
+        match (
+            it.next().and_then(|o| o.trim().parse::<usize>().ok()),
+            it.next().and_then(|l| l.trim().parse::<usize>().ok()),
+        ) {
+            (Some(off), Some(len)) if len > 0 => pieces.push(Piece::Slice(off, len)),
+            _ => return Err(format!("{{{}}} is not {{OFFSET:LEN}}", slice)),
+        }
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Lit(rest.to_string()));
+    }
+    Ok(pieces)
+}
+
+// Arguments can not contain NUL, so the slice ends at the first one.
+fn get_slice(buf: &[u8], off: usize, len: usize) -> &[u8] {
+    let begin = off.min(buf.len());
+    let end = off.saturating_add(len).min(buf.len());
+    let s = &buf[begin..end];
+    match s.iter().position(|&b| b == 0) {
+        Some(n) => &s[..n],
+        None => s,
+    }
+}
+
+fn render_pieces(pieces: &[Piece], buf: &[u8], key: &str, taint: &mut String) -> Vec<u8> {
+    let mut out = vec![];
+    for p in pieces {
+        match p {
+            Piece::Lit(l) => out.extend_from_slice(l.as_bytes()),
+            Piece::Slice(off, len) => {
+                let s = get_slice(buf, *off, *len);
+                if !s.is_empty() {
+                    taint.push_str(&format!("{}:{}:{}:{};", key, out.len(), off, s.len()));
+                }
+                out.extend_from_slice(s);
+            },
+        }
+    }
+    out
+}
+
+impl ArgTemplate {
+    // `args` are split by whitespace, and `envs` are NAME=VALUE.
+    pub fn parse(args: &str, envs: &[String]) -> Result<Self, String> {
+        let args = args
+            .split_whitespace()
+            .map(parse_pieces)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut env_list = vec![];
+        for e in envs {
+            let mut kv = e.splitn(2, '=');
+            let (name, val) = match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) => (k.trim(), v),
+                _ => return Err(format!("{} is not NAME=VALUE", e)),
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("Invalid name of environment variable: {}", name));
+            }
+            env_list.push((name.to_string(), parse_pieces(val)?));
+        }
+        Ok(Self {
+            args,
+            envs: env_list,
+        })
+    }
+
+    pub fn has_input_arg(&self) -> bool {
+        self.args
+            .iter()
+            .any(|a| a.len() == 1 && a[0] == Piece::Lit("@@".to_string()))
+    }
+
+    pub fn set_input_file(&mut self, file: &str) {
+        for a in &mut self.args {
+            if a.len() == 1 && a[0] == Piece::Lit("@@".to_string()) {
+                a[0] = Piece::Lit(file.to_string());
+            }
+        }
+    }
+
+    // The arguments come after `num_fixed` ones in argv, including argv[0].
+    pub fn render(&self, buf: &[u8], num_fixed: usize) -> RenderedArgs {
+        let mut taint_args = String::new();
+        let mut taint_envs = String::new();
+        let args = self
+            .args
+            .iter()
+            .enumerate()
+            .map(|(i, a)| render_pieces(a, buf, &(num_fixed + i).to_string(), &mut taint_args))
+            .collect();
+        let envs = self
+            .envs
+            .iter()
+            .map(|(name, v)| (name.clone(), render_pieces(v, buf, name, &mut taint_envs)))
+            .collect();
+        RenderedArgs {
+            input: ArgInput { args, envs },
+            taint_args,
+            taint_envs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arg_template() {
+        assert!(ArgTemplate::parse("-x {0:16", &[]).is_err());
+        assert!(ArgTemplate::parse("-x {a:1}", &[]).is_err());
+        assert!(ArgTemplate::parse("", &["A-B=1".to_string()]).is_err());
+
+        let mut t =
+            ArgTemplate::parse("-x {0:4} --n={4:2} @@", &["MODE=m{6:8}".to_string()]).unwrap();
+        assert!(t.has_input_arg());
+        t.set_input_file("/tmp/cur_input");
+        let r = t.render(b"abcdef\0gh", 1);
+        assert_eq!(
+            r.input.args,
+            vec![b"-x".to_vec(), b"abcd".to_vec(), b"--n=ef".to_vec(), b"/tmp/cur_input".to_vec()]
+        );
+        // The NUL ends the slice.
+        assert_eq!(r.input.envs, vec![("MODE".to_string(), b"m".to_vec())]);
+        assert_eq!(r.taint_args, "2:0:0:4;3:4:4:2;");
+        assert_eq!(r.taint_envs, "");
+        // A short input gives empty slices, with the same number of arguments.
+        let r = t.render(b"", 1);
+        assert_eq!(r.input.args.len(), 4);
+        assert_eq!(r.input.args[1], b"".to_vec());
+    }
+}
@@ -0,0 +1,67 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::tmin_main;
+
+fn main() {
+    let matches = App::new("angora-tmin")
+        .version(crate_version!())
+        .about("Shrink an input while keeping the program's behavior on it, e.g. crash or hang.")
+        .arg(Arg::with_name("input_file")
+             .short("i")
+             .long("input")
+             .value_name("FILE")
+             .help("Sets the input to minimize")
+             .takes_value(true)
+             .required(true))
+        .arg(Arg::with_name("output_file")
+             .short("o")
+             .long("output")
+             .value_name("FILE")
+             .help("Sets the file of the minimized input")
+             .takes_value(true)
+             .required(true))
+        .arg(Arg::with_name("track_target")
+             .short("t")
+             .long("track")
+             .value_name("PROM")
+             .help("Sets the target (USE_TRACK) for tracking, then the bytes not tainted into any comparison are pruned first")
+             .takes_value(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+             .short("M")
+             .long("memory_limit")
+             .value_name("MEM")
+             .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+             .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+             .short("T")
+             .long("time_limit")
+             .value_name("TIME")
+             .help("time limit for programs, default is 1(s)")
+             .takes_value(true))
+        .arg(Arg::with_name("exact_trace")
+             .short("e")
+             .long("exact_trace")
+             .help("Keep the edge trace of crashes and hangs as well, not only the status"))
+        .get_matches();
+
+    tmin_main(
+        matches.value_of("input_file").unwrap(),
+        matches.value_of("output_file").unwrap(),
+        matches.values_of_lossy("pargs").unwrap(),
+        matches.value_of("track_target"),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+        matches.occurrences_of("exact_trace") != 0,
+    );
+}
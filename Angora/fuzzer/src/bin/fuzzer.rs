@@ -4,7 +4,8 @@ use clap::{App, Arg};
 
 extern crate angora;
 extern crate angora_common;
-use angora::fuzz_main;
+use angora::{fuzz_main, FuzzOpt, StopCriteria};
+use std::{panic, process};
 
 fn main() {
     let matches = App::new("angora-fuzzer")
@@ -37,7 +38,7 @@ fn main() {
              .help("Sets the target (USE_TRACK or USE_PIN) for tracking, including taints, cmps.  Only set in LLVM mode.")
              .takes_value(true))
         .arg(Arg::with_name("pargs")
-            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename from Angora.")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename from Angora, and \"@@1\", \"@@2\", .. with the files of the parts of a multi-file input.")
             .required(true)
             .multiple(true)
             .allow_hyphen_values(true)
@@ -83,6 +84,108 @@ fn main() {
              .short("X")
              .long("enable_dict_mutation")
              .help("Enable dict mutation"))
+        .arg(Arg::with_name("focus_havoc")
+             .short("F")
+             .long("focus_havoc")
+             .help("Focus AFL's havoc mutation on the bytes of unsolved conditions"))
+        .arg(Arg::with_name("shm_input")
+             .long("shm_input")
+             .help("Pass the inputs in shared memory instead of the file, read by __angora_shm_input() or read(2) on stdin with ANGORA_SHM_READ=1"))
+        .arg(Arg::with_name("shm_input_file")
+             .long("shm_input_file")
+             .requires("shm_input")
+             .help("With --shm_input, also write the inputs to the file, for the programs that read it too"))
+        .arg(Arg::with_name("net")
+             .long("net")
+             .value_name("PROTO:PORT")
+             .help("Send the inputs to the program over the network on localhost, e.g. tcp:8080 or udp:5353")
+             .takes_value(true))
+        .arg(Arg::with_name("arg_template")
+             .long("arg_template")
+             .value_name("TEMPLATE")
+             .help("Append arguments taken from the input to the program, {OFFSET:LEN} is a slice of the input, e.g. \"-x {0:16} @@\"")
+             .takes_value(true))
+        .arg(Arg::with_name("env_template")
+             .long("env_template")
+             .value_name("NAME=TEMPLATE")
+             .help("Set an environment variable taken from the input, e.g. LANG={16:8}. Can be given multiple times.")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1))
+        .arg(Arg::with_name("net_response")
+             .long("net_response")
+             .requires("net")
+             .help("Wait for the response of the program, or the connection to be closed, before stopping it"))
+        .arg(Arg::with_name("custom_mutator")
+             .long("custom_mutator")
+             .value_name("LIB")
+             .help("Load a custom mutator (shared library with AFL++'s afl_custom_* API)")
+             .takes_value(true))
+        .arg(Arg::with_name("queue_policy")
+             .long("queue_policy")
+             .value_name("Policy")
+             .help("How to prioritize the conditions in the queue, default is legacy")
+             .possible_values(&["legacy", "fast_rare"]))
+        .arg(Arg::with_name("triage_crashes")
+             .long("triage_crashes")
+             .help("Re-run the crashes with symbolized sanitizers, and bucket them by stack in crashes/triage.json"))
+        .arg(Arg::with_name("sanitizers")
+             .long("sanitizers")
+             .value_name("NAMES")
+             .help("Take the exit codes of these sanitizers as crashes, for the programs where they are not found, e.g. stripped ones")
+             .takes_value(true)
+             .use_delimiter(true)
+             .possible_values(&["asan", "msan", "ubsan", "tsan", "lsan"]))
+        .arg(Arg::with_name("checkpoint_interval")
+             .long("checkpoint_interval")
+             .value_name("MIN")
+             .help("Save a checkpoint to resume from with `-i -` every MIN minutes, default is 10. 0 means only on exit.")
+             .takes_value(true))
+        .arg(Arg::with_name("target")
+             .long("target")
+             .value_name("FILE")
+             .help("Direct the fuzzing toward the target cmpids in FILE, one per line or the [ID] lines of ANGORA_OUTPUT_COND_LOC=1")
+             .takes_value(true))
+        .arg(Arg::with_name("revive_interval")
+             .long("revive_interval")
+             .value_name("MIN")
+             .help("Retry the unsolvable and timed-out conditions after MIN minutes, default is 30. 0 means only when reached by other inputs.")
+             .takes_value(true))
+        .arg(Arg::with_name("config")
+             .long("config")
+             .value_name("FILE")
+             .help("Load the tuning options from a TOML file, the effective ones are saved in the output directory as config.toml")
+             .takes_value(true))
+        .arg(Arg::with_name("set")
+             .long("set")
+             .value_name("KEY=VALUE")
+             .help("Override a tuning option, e.g. --set max_input_len=20000. Can be given multiple times.")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1))
+        .arg(Arg::with_name("stop_time")
+             .long("stop_time")
+             .value_name("SEC")
+             .help("Stop after SEC seconds")
+             .takes_value(true))
+        .arg(Arg::with_name("stop_execs")
+             .long("stop_execs")
+             .value_name("N")
+             .help("Stop after N executions")
+             .takes_value(true))
+        .arg(Arg::with_name("stop_on_crash")
+             .long("stop_on_crash")
+             .help("Stop at the first crash"))
+        .arg(Arg::with_name("stop_edges")
+             .long("stop_edges")
+             .value_name("N")
+             .help("Stop when N edges are covered")
+             .takes_value(true))
+        .arg(Arg::with_name("stop_no_new_path")
+             .long("stop_no_new_path")
+             .value_name("MIN")
+             .help("Stop if no new path is found for MIN minutes")
+             .takes_value(true))
         /*.arg(Arg::with_name("dict_mutation")
              .short("X")
              .long("dict")
@@ -93,20 +196,51 @@ fn main() {
         */
         .get_matches();
 
-    fuzz_main(
-        matches.value_of("mode").unwrap_or("llvm"),
-        matches.value_of("input_dir").unwrap(),
-        matches.value_of("output_dir").unwrap(),
-        matches.value_of("track_target").unwrap_or("-"),
-        matches.values_of_lossy("pargs").unwrap(),
-        value_t!(matches, "thread_jobs", usize).unwrap_or(1),
-        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
-        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
-        matches.value_of("search_method").unwrap_or("gd"),
-        matches.occurrences_of("sync_afl") > 0,
-        matches.occurrences_of("disable_afl_mutation") == 0,
-        matches.occurrences_of("disable_exploitation") == 0,
-        matches.occurrences_of("enable_dict_mutation") != 0,
-        //matches.value_of("dict_mutation").unwrap_or("nodict"),
-    );
+    let stop = StopCriteria {
+        max_time: value_t!(matches, "stop_time", u64).ok(),
+        max_execs: value_t!(matches, "stop_execs", usize).ok(),
+        on_crash: matches.occurrences_of("stop_on_crash") != 0,
+        edges: value_t!(matches, "stop_edges", usize).ok(),
+        no_new_path: value_t!(matches, "stop_no_new_path", u64).ok(),
+    };
+
+    let opt = FuzzOpt {
+        mode: matches.value_of("mode").unwrap_or("llvm").to_string(),
+        in_dir: matches.value_of("input_dir").unwrap().to_string(),
+        out_dir: matches.value_of("output_dir").unwrap().to_string(),
+        track_target: matches.value_of("track_target").unwrap_or("-").to_string(),
+        pargs: matches.values_of_lossy("pargs").unwrap(),
+        num_jobs: value_t!(matches, "thread_jobs", usize).unwrap_or(1),
+        mem_limit: value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        time_limit: value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+        search_method: matches.value_of("search_method").unwrap_or("gd").to_string(),
+        sync_afl: matches.occurrences_of("sync_afl") > 0,
+        enable_afl: matches.occurrences_of("disable_afl_mutation") == 0,
+        enable_exploitation: matches.occurrences_of("disable_exploitation") == 0,
+        enable_dict: matches.occurrences_of("enable_dict_mutation") != 0,
+        custom_mutator: matches.value_of("custom_mutator").map(|s| s.to_string()),
+        enable_focus_havoc: matches.occurrences_of("focus_havoc") != 0,
+        shm_input: matches.occurrences_of("shm_input") != 0,
+        shm_input_file: matches.occurrences_of("shm_input_file") != 0,
+        net: matches.value_of("net").map(|s| s.to_string()),
+        net_response: matches.occurrences_of("net_response") != 0,
+        arg_template: matches.value_of("arg_template").map(|s| s.to_string()),
+        env_templates: matches.values_of_lossy("env_template").unwrap_or_default(),
+        queue_policy: matches.value_of("queue_policy").unwrap_or("legacy").to_string(),
+        checkpoint_interval: value_t!(matches, "checkpoint_interval", u64)
+            .unwrap_or(angora_common::config::CHECKPOINT_INTERVAL),
+        triage_crashes: matches.occurrences_of("triage_crashes") != 0,
+        sanitizers: matches.values_of_lossy("sanitizers").unwrap_or_default(),
+        target: matches.value_of("target").map(|s| s.to_string()),
+        revive_interval: value_t!(matches, "revive_interval", u64)
+            .unwrap_or(angora_common::config::REVIVE_INTERVAL),
+        config_file: matches.value_of("config").map(|s| s.to_string()),
+        config_overrides: matches.values_of_lossy("set").unwrap_or_default(),
+        stop_criteria: stop,
+        //dict_mutation: matches.value_of("dict_mutation").unwrap_or("nodict"),
+    };
+
+    let code = panic::catch_unwind(move || fuzz_main(opt))
+    .unwrap_or(angora_common::defs::EXIT_ERROR);
+    process::exit(code);
 }
@@ -30,9 +30,11 @@ fn main() {
         &envs,
         0 as RawFd,
         false,
-        false,
+        &[],
         TIME_LIMIT,
         MEM_LIMIT,
+        angora_common::config::FORKSRV_INIT_TIMEOUT,
+        None,
     );
 
     let init_t = SystemTime::now();
@@ -1,5 +1,6 @@
 use crate::executor::StatusType;
 use angora_common::{config::BRANCHES_SIZE, shm::SHM};
+use serde_derive::{Deserialize, Serialize};
 use std::{
     self,
     sync::{
@@ -71,6 +72,49 @@ impl GlobalBranches {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct BranchesCheckpoint {
+    virgin_branches: Vec<u8>,
+    tmouts_branches: Vec<u8>,
+    crashes_branches: Vec<u8>,
+    density: usize,
+}
+
+impl GlobalBranches {
+    pub fn checkpoint(&self) -> BranchesCheckpoint {
+        BranchesCheckpoint {
+            virgin_branches: self.virgin_branches.read().unwrap().to_vec(),
+            tmouts_branches: self.tmouts_branches.read().unwrap().to_vec(),
+            crashes_branches: self.crashes_branches.read().unwrap().to_vec(),
+            density: self.density.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn restore(&self, c: &BranchesCheckpoint) {
+        // Check all the maps before writing any of them, so they are restored together or not at all.
+        if c.virgin_branches.len() != BRANCHES_SIZE
+            || c.tmouts_branches.len() != BRANCHES_SIZE
+            || c.crashes_branches.len() != BRANCHES_SIZE
+        {
+            warn!("The size of branches in checkpoint is mismatched, start with empty branches.");
+            return;
+        }
+        self.virgin_branches
+            .write()
+            .unwrap()
+            .copy_from_slice(&c.virgin_branches);
+        self.tmouts_branches
+            .write()
+            .unwrap()
+            .copy_from_slice(&c.tmouts_branches);
+        self.crashes_branches
+            .write()
+            .unwrap()
+            .copy_from_slice(&c.crashes_branches);
+        self.density.store(c.density, Ordering::Relaxed);
+    }
+}
+
 pub struct Branches {
     global: Arc<GlobalBranches>,
     trace: SHM<BranchBuf>,
@@ -90,7 +134,11 @@ impl Branches {
         self.trace.get_id()
     }
 
-    fn get_path(&self) -> Vec<(usize, u8)> {
+    pub fn trace_mut(&mut self) -> &mut BranchBuf {
+        &mut self.trace
+    }
+
+    pub fn get_path(&self) -> Vec<(usize, u8)> {
         let mut path = Vec::<(usize, u8)>::new();
         let buf_plus: &BranchBufPlus = cast!(&*self.trace);
         let buf: &BranchBuf = &*self.trace;
@@ -126,7 +174,7 @@ impl Branches {
         let gb_map = match status {
             StatusType::Normal => &self.global.virgin_branches,
             StatusType::Timeout => &self.global.tmouts_branches,
-            StatusType::Crash => &self.global.crashes_branches,
+            StatusType::Crash(_) => &self.global.crashes_branches,
             _ => {
                 return (false, false, 0);
             },
@@ -188,6 +236,7 @@ impl std::fmt::Debug for Branches {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::executor::CrashKind;
 
     #[test]
     #[ignore]
@@ -196,7 +245,7 @@ mod tests {
         let mut br = Branches::new(global_branches);
         assert_eq!(br.has_new(StatusType::Normal), (false, false, 0));
         assert_eq!(br.has_new(StatusType::Timeout), (false, false, 0));
-        assert_eq!(br.has_new(StatusType::Crash), (false, false, 0));
+        assert_eq!(br.has_new(StatusType::Crash(CrashKind::Signal)), (false, false, 0));
     }
 
     #[test]
@@ -216,4 +265,27 @@ mod tests {
         assert_eq!(path[2].1, COUNT_LOOKUP[3]);
         assert_eq!(br.has_new(StatusType::Normal), (true, true, 3));
     }
+
+    #[test]
+    fn branch_checkpoint() {
+        // The maps are built on the stack before being boxed.
+        let t = std::thread::Builder::new().stack_size(32 << 20).spawn(|| {
+            let global_branches = GlobalBranches::new();
+            global_branches.virgin_branches.write().unwrap()[7] = 1;
+            global_branches.density.store(1, Ordering::Relaxed);
+            let mut c = global_branches.checkpoint();
+            let restored = GlobalBranches::new();
+            restored.restore(&c);
+            assert_eq!(restored.virgin_branches.read().unwrap()[7], 1);
+            assert_eq!(restored.tmouts_branches.read().unwrap()[7], 255);
+            assert_eq!(restored.get_covered_branches(), 1);
+
+            c.crashes_branches.truncate(1);
+            let fresh = GlobalBranches::new();
+            fresh.restore(&c);
+            assert_eq!(fresh.virgin_branches.read().unwrap()[7], 255);
+            assert_eq!(fresh.get_covered_branches(), 0);
+        });
+        assert!(t.unwrap().join().is_ok());
+    }
 }
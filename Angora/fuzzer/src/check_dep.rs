@@ -1,4 +1,5 @@
-use crate::command::CommandOpt;
+use crate::{command::CommandOpt, executor::CrashKind};
+use angora_common::defs;
 use memmap;
 use std::{fs::File, io::prelude::*, path::Path};
 use twoway;
@@ -43,9 +44,30 @@ fn containt_string(f_data: &memmap::Mmap, s: &str) -> bool {
     twoway::find_bytes(&f_data[..], s.as_bytes()).is_some()
 }
 
-pub fn check_asan(target: &str) -> bool {
+// The sanitizers built in the program.
+pub fn check_sanitizers(target: &str) -> Vec<CrashKind> {
     let f_data = mmap_file(target);
-    containt_string(&f_data, "libasan.so") || containt_string(&f_data, "__msan_init")
+    let mut sanitizers: Vec<CrashKind> = CrashKind::ALL
+        .iter()
+        .filter(|k| k.signatures().iter().any(|s| containt_string(&f_data, s)))
+        .cloned()
+        .collect();
+    if sanitizers.contains(&CrashKind::Asan) {
+        sanitizers.retain(|&k| k != CrashKind::Lsan);
+    }
+    sanitizers
+}
+
+// The program uses __ANGORA_LOOP.
+pub fn check_persistent(target: &str) -> bool {
+    let f_data = mmap_file(target);
+    containt_string(&f_data, defs::PERSIST_SIG)
+}
+
+// The program uses __ANGORA_INIT().
+pub fn check_deferred(target: &str) -> bool {
+    let f_data = mmap_file(target);
+    containt_string(&f_data, defs::DEFER_SIG)
 }
 
 fn check_fast(target: &str) {
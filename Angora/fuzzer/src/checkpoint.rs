@@ -0,0 +1,81 @@
+// Save the state of the campaign in the output directory,
+// so that restarting with `-i -` continues where it stopped instead of
+// re-running and re-tracking all the inputs.
+use crate::{
+    branches::{BranchesCheckpoint, GlobalBranches},
+    depot::{Depot, DepotCheckpoint},
+    stats::{ChartStats, StatsCheckpoint},
+};
+use angora_common::defs;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufReader, BufWriter},
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    depot: DepotCheckpoint,
+    branches: BranchesCheckpoint,
+    stats: StatsCheckpoint,
+}
+
+impl Checkpoint {
+    pub fn save(
+        out_dir: &Path,
+        depot: &Arc<Depot>,
+        gb: &Arc<GlobalBranches>,
+        stats: &Arc<RwLock<ChartStats>>,
+    ) {
+        // The branches first, since the threads keep running, and the inputs
+        // that find new edges are saved after the edges are marked.
+        let branches = gb.checkpoint();
+        let c = Self {
+            depot: depot.checkpoint(),
+            branches,
+            stats: stats.read().unwrap().checkpoint(),
+        };
+
+        // Write to a temporary file first, in case we are killed while writing.
+        let tmp_path = out_dir.join(format!("{}.tmp", defs::CHECKPOINT_FILE));
+        let f = match fs::File::create(&tmp_path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Could not create checkpoint file: {:?}", e);
+                return;
+            },
+        };
+        if let Err(e) = bincode::serialize_into(BufWriter::new(f), &c) {
+            warn!("Could not write checkpoint: {:?}", e);
+            return;
+        }
+        if let Err(e) = fs::rename(&tmp_path, out_dir.join(defs::CHECKPOINT_FILE)) {
+            warn!("Could not save checkpoint: {:?}", e);
+        }
+    }
+
+    pub fn load(out_dir: &Path) -> Option<Self> {
+        let path = out_dir.join(defs::CHECKPOINT_FILE);
+        let f = fs::File::open(&path).ok()?;
+        match bincode::deserialize_from(BufReader::new(f)) {
+            Ok(c) => Some(c),
+            Err(e) => {
+                warn!("Could not load checkpoint {:?}: {:?}", path, e);
+                None
+            },
+        }
+    }
+
+    pub fn restore(
+        self,
+        depot: &Arc<Depot>,
+        gb: &Arc<GlobalBranches>,
+        stats: &Arc<RwLock<ChartStats>>,
+    ) {
+        depot.restore(self.depot);
+        gb.restore(&self.branches);
+        stats.write().unwrap().restore(&self.stats);
+    }
+}
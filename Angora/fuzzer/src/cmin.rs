@@ -0,0 +1,114 @@
+// Corpus minimization, like afl-cmin:
+// keep the smallest subset of inputs that covers every (edge, hit-count bucket)
+// the whole corpus covers.
+use crate::executor::{StatusType, TraceRunner};
+use angora_common::config;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+static TMP_DIR: &str = ".cmin_tmp";
+
+// (edge index, bucket of hit count)
+type Tuple = (usize, u8);
+
+// Greedy selection: for each tuple, from the rarest to the most common,
+// keep the smallest input having it if it is not covered yet.
+// `inputs` are (size, tuples), and the indices of the kept ones are returned.
+fn select_inputs(inputs: &[(usize, Vec<Tuple>)]) -> Vec<usize> {
+    let mut owners: HashMap<Tuple, Vec<usize>> = HashMap::new();
+    for (i, (_, tuples)) in inputs.iter().enumerate() {
+        for t in tuples {
+            owners.entry(*t).or_default().push(i);
+        }
+    }
+
+    let mut tuples: Vec<(&Tuple, &Vec<usize>)> = owners.iter().collect();
+    tuples.sort_by_key(|(t, ids)| (ids.len(), **t));
+
+    let mut covered = HashSet::new();
+    let mut selected = vec![];
+    for (t, ids) in tuples {
+        if covered.contains(t) {
+            continue;
+        }
+        let best = *ids
+            .iter()
+            .min_by_key(|&&i| (inputs[i].0, i))
+            .unwrap();
+        covered.extend(inputs[best].1.iter().cloned());
+        selected.push(best);
+    }
+    selected.sort();
+    selected
+}
+
+pub fn cmin_main(in_dir: &str, out_dir: &str, pargs: Vec<String>, mem_limit: u64, time_limit: u64) {
+    pretty_env_logger::init();
+
+    let out_dir = Path::new(out_dir);
+    fs::create_dir(out_dir).expect("Output directory has existed!");
+    let mut runner = TraceRunner::new(&pargs, None, out_dir.join(TMP_DIR), mem_limit, time_limit);
+
+    let mut paths = vec![];
+    let mut inputs = vec![];
+    let entries = fs::read_dir(in_dir).expect("read_dir call failed");
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let buf = match fs::read(&path) {
+            Ok(buf) => buf,
+            Err(e) => {
+                warn!("Could not read {:?}: {:?}", path, e);
+                continue;
+            },
+        };
+        if buf.len() > config::MAX_INPUT_LEN {
+            warn!("Input discarded, too long: {:?}", path);
+            continue;
+        }
+        let (status, tuples) = runner.run(&buf);
+        if status != StatusType::Normal {
+            warn!("Input discarded, status is {:?}: {:?}", status, path);
+            continue;
+        }
+        paths.push(path);
+        inputs.push((buf.len(), tuples));
+    }
+
+    let selected = select_inputs(&inputs);
+    for &i in &selected {
+        let path = &paths[i];
+        fs::copy(path, out_dir.join(path.file_name().unwrap())).expect("Could not copy input.");
+    }
+    let num_tuples: HashSet<&Tuple> = inputs.iter().flat_map(|(_, t)| t.iter()).collect();
+    println!(
+        "Keep {} of {} inputs, covering {} tuples.",
+        selected.len(),
+        inputs.len(),
+        num_tuples.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_inputs() {
+        let inputs = vec![
+            (10, vec![(1, 1), (2, 1)]),
+            (5, vec![(1, 1)]),
+            (3, vec![(2, 1)]),
+            (100, vec![(1, 1), (2, 1), (3, 2)]),
+            (1, vec![(3, 1)]),
+        ];
+        // (3, 2) only in 3, which covers 1 and 2 as well.
+        assert_eq!(select_inputs(&inputs), vec![3, 4]);
+        assert_eq!(select_inputs(&inputs[..3]), vec![1, 2]);
+    }
+}
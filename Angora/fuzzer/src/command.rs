@@ -1,4 +1,10 @@
-use crate::{check_dep, search, tmpfs};
+use crate::{
+    arg_template::ArgTemplate,
+    check_dep,
+    executor::{has_port_arg, subst_port_arg, CrashKind, NetOpt},
+    fuzz_config::{Config, FuzzOpt},
+    multi_input, search, tmpfs,
+};
 use angora_common::defs;
 use std::{
     env,
@@ -49,27 +55,40 @@ pub struct CommandOpt {
     pub mem_limit: u64,
     pub time_limit: u64,
     pub is_raw: bool,
-    pub uses_asan: bool,
+    // The sanitizers built in the fast program.
+    pub sanitizers: Vec<CrashKind>,
+    pub is_persistent: bool,
+    pub is_deferred: bool,
     pub ld_library: String,
     pub enable_afl: bool,
     pub enable_exploitation: bool,
     pub enable_dict: bool,
+    pub custom_mutator: Option<String>,
+    pub enable_focus_havoc: bool,
+    pub shm_input: bool,
+    // Also write the file with --shm_input.
+    pub shm_input_file: bool,
+    // The program reads the input from the network.
+    pub net: Option<NetOpt>,
+    // The arguments and envs from the input, after the ones in `main` and `track`.
+    pub arg_template: Option<ArgTemplate>,
+    // The number of files of each input (@@1, @@2, ..), 0 if it is a single file.
+    pub num_parts: usize,
+    pub config: Config,
 }
 
 impl CommandOpt {
     pub fn new(
-        mode: &str,
-        track_target: &str,
-        pargs: Vec<String>,
+        opt: &FuzzOpt,
         out_dir: &Path,
-        search_method: &str,
-        mut mem_limit: u64,
-        time_limit: u64,
-        enable_afl: bool,
-        enable_exploitation: bool,
-        enable_dict: bool,
-    ) -> Self {
-        let mode = InstrumentationMode::from(mode);
+        net: Option<NetOpt>,
+        arg_template: Option<ArgTemplate>,
+        config: Config,
+) -> Self {
+        let mode = InstrumentationMode::from(opt.mode.as_str());
+        let track_target = opt.track_target.as_str();
+        let pargs = &opt.pargs;
+        let mut mem_limit = opt.mem_limit;
         
         let tmp_dir = out_dir.join(TMP_DIR);
         tmpfs::create_tmpfs_dir(&tmp_dir);
@@ -83,7 +102,10 @@ impl CommandOpt {
 
         let track_path = tmp_dir.join(TRACK_FILE).to_str().unwrap().to_owned();
 
-        let has_input_arg = pargs.contains(&"@@".to_string());
+        let num_parts = multi_input::num_parts(pargs);
+        let has_input_arg = pargs.contains(&"@@".to_string())
+            || num_parts > 0
+            || arg_template.as_ref().is_some_and(|t| t.has_input_arg());
 
         let clang_lib = Command::new("llvm-config")
             .arg("--libdir")
@@ -101,11 +123,34 @@ impl CommandOpt {
         let mut tmp_args = pargs.clone();
         let main_bin = tmp_args[0].clone();
         let main_args: Vec<String> = tmp_args.drain(1..).collect();
-        let uses_asan = check_dep::check_asan(&main_bin);
-        if uses_asan && mem_limit != 0 {
-            warn!("The program compiled with ASAN, set MEM_LIMIT to 0 (unlimited)");
+        let mut sanitizers = check_dep::check_sanitizers(&main_bin);
+        // They are not found in stripped programs.
+        for name in &opt.sanitizers {
+            match CrashKind::from_name(name) {
+                Some(s) if !sanitizers.contains(&s) => sanitizers.push(s),
+                Some(_) => {},
+                None => {
+                    error!("Unknown sanitizer: {}", name);
+                    panic!();
+                },
+            }
+        }
+        if !sanitizers.is_empty() {
+            let names: Vec<&str> = sanitizers.iter().map(|s| s.name()).collect();
+            info!("The program is compiled with sanitizers: {}", names.join(", "));
+        }
+        if sanitizers.iter().any(|s| s.needs_unlimited_memory()) && mem_limit != 0 {
+            warn!("The program compiled with ASAN, MSAN or TSAN, set MEM_LIMIT to 0 (unlimited)");
             mem_limit = 0;
         }
+        let is_persistent = check_dep::check_persistent(&main_bin);
+        if is_persistent {
+            info!("The program uses persistent mode.");
+        }
+        let is_deferred = check_dep::check_deferred(&main_bin);
+        if is_deferred {
+            info!("The program defers the fork server.");
+        }
 
         let track_bin;
         let mut track_args = Vec::<String>::new();
@@ -142,16 +187,63 @@ impl CommandOpt {
             out_file: out_file,
             forksrv_socket_path,
             track_path,
-            is_stdin: !has_input_arg,
-            search_method: search::parse_search_method(search_method),
+            is_stdin: !has_input_arg && net.is_none(),
+            search_method: search::parse_search_method(&opt.search_method),
             mem_limit,
-            time_limit,
-            uses_asan,
+            time_limit: opt.time_limit,
+            sanitizers,
+            is_persistent,
+            is_deferred,
             is_raw: true,
             ld_library,
-            enable_afl,
-            enable_exploitation,
-            enable_dict,
+            enable_afl: opt.enable_afl,
+            enable_exploitation: opt.enable_exploitation,
+            enable_dict: opt.enable_dict,
+            custom_mutator: opt.custom_mutator.clone(),
+            enable_focus_havoc: opt.enable_focus_havoc,
+            shm_input: opt.shm_input,
+            shm_input_file: opt.shm_input_file,
+            net,
+            arg_template,
+            num_parts,
+            config,
+        }
+    }
+
+    // For the executors with an in-process backend, which run no programs.
+    #[cfg(test)]
+    pub fn for_test(out_dir: &Path) -> Self {
+        let tmp_dir = out_dir.join(TMP_DIR);
+        let path = |f: &str| tmp_dir.join(f).to_str().unwrap().to_owned();
+        Self {
+            mode: InstrumentationMode::LLVM,
+            id: 0,
+            main: (String::from("target"), vec![]),
+            track: (String::from("target.track"), vec![]),
+            out_file: path(INPUT_FILE),
+            forksrv_socket_path: path(FORKSRV_SOCKET_FILE),
+            track_path: path(TRACK_FILE),
+            tmp_dir,
+            is_stdin: true,
+            search_method: search::SearchMethod::Gd,
+            mem_limit: 0,
+            time_limit: 1,
+            is_raw: false,
+            sanitizers: vec![],
+            is_persistent: false,
+            is_deferred: false,
+            ld_library: String::new(),
+            enable_afl: false,
+            enable_exploitation: false,
+            enable_dict: false,
+            custom_mutator: None,
+            enable_focus_havoc: false,
+            shm_input: false,
+            shm_input_file: false,
+            net: None,
+            arg_template: None,
+            num_parts: 0,
+            config: Config::default(),
         }
     }
 
@@ -161,15 +253,27 @@ impl CommandOpt {
         let new_forksrv_socket_path = format!("{}_{}", &cmd_opt.forksrv_socket_path, id);
         let new_track_path = format!("{}_{}", &cmd_opt.track_path, id);
         if !self.is_stdin {
-            for arg in &mut cmd_opt.main.1 {
+            let subst = |arg: &mut String| {
                 if arg == "@@" {
                     *arg = new_file.clone();
+                } else if let Some(n) = multi_input::part_of_arg(arg) {
+                    *arg = multi_input::part_file(&new_file, n);
                 }
+            };
+            cmd_opt.main.1.iter_mut().for_each(subst);
+            cmd_opt.track.1.iter_mut().for_each(subst);
+            if let Some(ref mut t) = cmd_opt.arg_template {
+                t.set_input_file(&new_file);
             }
-            for arg in &mut cmd_opt.track.1 {
-                if arg == "@@" {
-                    *arg = new_file.clone();
-                }
+        }
+        // Each executor listens on its own port, if the program is told by the arguments.
+        if let Some(ref mut net) = cmd_opt.net {
+            if has_port_arg(&cmd_opt.main.1) {
+                net.port += id as u16;
+                let port = net.port;
+                let subst = |arg: &mut String| subst_port_arg(arg, port);
+                cmd_opt.main.1.iter_mut().for_each(subst);
+                cmd_opt.track.1.iter_mut().for_each(subst);
             }
         }
         cmd_opt.id = id;
@@ -1,5 +1,5 @@
 use crate::{cond_stmt::CondStmt, mut_input::offsets::*};
-use angora_common::{config, defs};
+use angora_common::defs;
 use std;
 use serde_derive::{Deserialize, Serialize};
 
@@ -23,9 +23,25 @@ impl Default for CondState {
 }
 
 impl CondStmt {
-    pub fn is_time_expired(&self) -> bool {
+    pub fn is_time_expired(&self, long_fuzz_time: usize) -> bool {
         ((self.state.is_det() || self.state.is_one_byte()) && !self.is_first_time())
-            || self.fuzz_times >= config::LONG_FUZZ_TIME
+            || self.fuzz_times >= long_fuzz_time
+    }
+
+    pub fn can_revive(&self, max_times: usize) -> bool {
+        !self.is_done() && self.is_discarded() && self.num_revivals < max_times
+    }
+
+    // Start over from the initial state, with the offsets it has.
+    pub fn revive(&mut self) {
+        self.state = if self.offsets.len() == 1 && self.offsets[0].end - self.offsets[0].begin == 1 {
+            CondState::OneByte
+        } else {
+            CondState::Offset
+        };
+        self.fuzz_times = 0;
+        self.num_revivals += 1;
+        self.discarded_at = 0;
     }
 }
 
@@ -134,3 +150,30 @@ impl NextState for CondStmt {
         self.state = CondState::Timeout;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angora_common::{config, tag::TagSeg};
+
+    #[test]
+    fn test_revive() {
+        let mut cond = CondStmt::new();
+        cond.offsets = vec![TagSeg {
+            sign: false,
+            begin: 3,
+            end: 4,
+        }];
+        assert!(!cond.can_revive(config::REVIVE_MAX_TIMES));
+        cond.to_unsolvable();
+        cond.fuzz_times = 5;
+        for _ in 0..config::REVIVE_MAX_TIMES {
+            assert!(cond.can_revive(config::REVIVE_MAX_TIMES));
+            cond.revive();
+            assert!(cond.state.is_one_byte());
+            assert_eq!(cond.fuzz_times, 0);
+            cond.to_timeout();
+        }
+        assert!(!cond.can_revive(config::REVIVE_MAX_TIMES));
+    }
+}
@@ -18,6 +18,12 @@ pub struct CondStmt {
     pub state: CondState,
     pub num_minimal_optima: usize,
     pub linear: bool,
+    // executions spent on it
+    pub num_exec: usize,
+    // times retried after being discarded
+    pub num_revivals: usize,
+    // seconds since the epoch, 0 if it is not discarded
+    pub discarded_at: u64,
 }
 
 impl PartialEq for CondStmt {
@@ -51,6 +57,9 @@ impl CondStmt {
             state: CondState::default(),
             num_minimal_optima: 0,
             linear: false,
+            num_exec: 0,
+            num_revivals: 0,
+            discarded_at: 0,
         }
     }
 
@@ -0,0 +1,97 @@
+// Keep the recently used inputs of the queue in memory,
+// the files in the queue directory are the backing store.
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Default)]
+pub struct InputCache {
+    capacity: usize,
+    size: usize,
+    tick: u64,
+    // id -> (buf, last used)
+    entries: HashMap<usize, (Vec<u8>, u64)>,
+    // last used -> id, the first one is evicted.
+    lru: BTreeMap<u64, usize>,
+    pub num_hits: usize,
+    pub num_misses: usize,
+}
+
+impl InputCache {
+    // `capacity` is in bytes, 0 for disabled.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn touch(&mut self, id: usize) {
+        self.tick += 1;
+        if let Some(e) = self.entries.get_mut(&id) {
+            self.lru.remove(&e.1);
+            e.1 = self.tick;
+            self.lru.insert(self.tick, id);
+        }
+    }
+
+    pub fn get(&mut self, id: usize) -> Option<Vec<u8>> {
+        if self.entries.contains_key(&id) {
+            self.num_hits += 1;
+            self.touch(id);
+            self.entries.get(&id).map(|e| e.0.clone())
+        } else {
+            self.num_misses += 1;
+            None
+        }
+    }
+
+    pub fn insert(&mut self, id: usize, buf: &[u8]) {
+        if self.capacity == 0 || buf.len() > self.capacity || self.entries.contains_key(&id) {
+            return;
+        }
+        while self.size + buf.len() > self.capacity {
+            let (&tick, &old) = match self.lru.iter().next() {
+                Some(x) => x,
+                None => break,
+            };
+            self.lru.remove(&tick);
+            if let Some(e) = self.entries.remove(&old) {
+                self.size -= e.0.len();
+            }
+        }
+        self.tick += 1;
+        self.size += buf.len();
+        self.entries.insert(id, (buf.to_vec(), self.tick));
+        self.lru.insert(self.tick, id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = InputCache::new(10);
+        cache.insert(0, &[0; 4]);
+        cache.insert(1, &[1; 4]);
+        assert_eq!(cache.get(0), Some(vec![0; 4]));
+        // 1 is the least recently used one.
+        cache.insert(2, &[2; 4]);
+        assert_eq!(cache.get(1), None);
+        assert_eq!(cache.get(0), Some(vec![0; 4]));
+        assert_eq!(cache.get(2), Some(vec![2; 4]));
+        assert_eq!(cache.size(), 8);
+        // Too large to be cached.
+        cache.insert(3, &[3; 11]);
+        assert_eq!(cache.get(3), None);
+        assert_eq!((cache.num_hits, cache.num_misses), (3, 2));
+
+        let mut disabled = InputCache::new(0);
+        disabled.insert(0, &[0]);
+        assert_eq!(disabled.get(0), None);
+    }
+}
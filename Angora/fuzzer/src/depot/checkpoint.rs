@@ -0,0 +1,123 @@
+use super::*;
+use super::depot::InputCond;
+use crate::{cond_stmt::CondStmt, mut_input::LenField};
+use angora_common::defs;
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path, sync::atomic::Ordering};
+
+// Everything the depot knows besides the files in the output directory.
+#[derive(Serialize, Deserialize)]
+pub struct DepotCheckpoint {
+    queue: Vec<(CondStmt, QPriority)>,
+    num_inputs: usize,
+    num_hangs: usize,
+    num_crashes: usize,
+    input_conds: HashMap<usize, Vec<InputCond>>,
+    len_fields: HashMap<usize, Vec<LenField>>,
+    meta: DepotMeta,
+    input_names: HashMap<usize, String>,
+}
+
+// The ids of the files in `dir`, named `id:NNNNNN,..`.
+fn ids_on_disk(dir: &Path) -> Vec<(usize, String)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let id = name
+                .strip_prefix("id:")?
+                .split(&[',', '.'][..])
+                .next()?
+                .parse::<usize>()
+                .ok()?;
+            Some((id, name))
+        })
+        .collect()
+}
+
+// After an unclean stop, there may be newer files than the checkpoint,
+// whose ids should not be taken again.
+fn next_id(num: usize, dir: &Path) -> usize {
+    ids_on_disk(dir)
+        .iter()
+        .map(|(id, _)| id + 1)
+        .fold(num, std::cmp::max)
+}
+
+impl Depot {
+    pub fn checkpoint(&self) -> DepotCheckpoint {
+        // Take the queue first, the same order as the fuzzing threads.
+        let queue = self
+            .lock_queue()
+            .iter()
+            .map(|(c, p)| (c.clone(), *p))
+            .collect();
+        let meta = self.lock_meta().clone();
+        let input_conds = self.lock_input_conds().clone();
+        let len_fields = self.lock_len_fields().clone();
+        let input_names = self.lock_input_names().clone();
+
+        DepotCheckpoint {
+            queue,
+            num_inputs: self.num_inputs.load(Ordering::Relaxed),
+            num_hangs: self.num_hangs.load(Ordering::Relaxed),
+            num_crashes: self.num_crashes.load(Ordering::Relaxed),
+            input_conds,
+            len_fields,
+            meta,
+            input_names,
+        }
+    }
+
+    pub fn restore(&self, c: DepotCheckpoint) {
+        {
+            let mut q = self.lock_queue();
+            q.clear();
+            for (cond, p) in c.queue {
+                q.push(cond, p);
+            }
+        }
+        *self.lock_meta() = c.meta;
+        *self.lock_input_conds() = c.input_conds;
+        *self.lock_len_fields() = c.len_fields;
+        {
+            // The newer inputs are not tracked, but they can still be
+            // picked by their names, e.g. for splicing.
+            let mut names = c.input_names;
+            for (id, name) in ids_on_disk(&self.dirs.inputs_dir) {
+                if id >= c.num_inputs && !name.ends_with(defs::OUTPUT_LOG_SUFFIX) {
+                    names.insert(id, name);
+                }
+            }
+            *self.lock_input_names() = names;
+        }
+        self.num_inputs
+            .store(next_id(c.num_inputs, &self.dirs.inputs_dir), Ordering::Relaxed);
+        self.num_hangs
+            .store(next_id(c.num_hangs, &self.dirs.hangs_dir), Ordering::Relaxed);
+        self.num_crashes
+            .store(next_id(c.num_crashes, &self.dirs.crashes_dir), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_id() {
+        let dir = std::env::temp_dir().join(format!("angora_test_next_id_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(next_id(3, &dir), 3);
+        for name in &["id:000004,src:000001,op:havoc", "id:000007,sig:11.log", "README.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        assert_eq!(next_id(3, &dir), 8);
+        assert_eq!(next_id(10, &dir), 10);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
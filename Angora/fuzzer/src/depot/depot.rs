@@ -1,19 +1,55 @@
 use super::*;
-use crate::{cond_stmt::CondStmt, executor::StatusType, search::interesting_val};
+use crate::{
+    cond_stmt::CondStmt, executor::StatusType, fuzz_config::Config, mut_input::LenField,
+    search::interesting_val,
+};
 use rand;
 use std::{
+    collections::HashMap,
     fs,
     io::prelude::*,
     mem,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Mutex,
+        Mutex, MutexGuard,
     },
+    time::{SystemTime, UNIX_EPOCH},
 };
 // https://crates.io/crates/priority-queue
-use angora_common::config;
+use angora_common::{defs, tag::TagSeg};
 use priority_queue::PriorityQueue;
+use serde_derive::{Deserialize, Serialize};
+
+// A tainted condition reached by an input: its key in the queue,
+// and the offsets of the input it depends on.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InputCond {
+    cmpid: u32,
+    context: u32,
+    order: u32,
+    offsets: Vec<TagSeg>,
+}
+
+impl InputCond {
+    fn new(cond: &CondStmt) -> Self {
+        Self {
+            cmpid: cond.base.cmpid,
+            context: cond.base.context,
+            order: cond.base.order,
+            offsets: cond.offsets.clone(),
+        }
+    }
+
+    // Only the fields the queue is keyed by are set.
+    fn key(&self) -> CondStmt {
+        let mut cond = CondStmt::new();
+        cond.base.cmpid = self.cmpid;
+        cond.base.context = self.context;
+        cond.base.order = self.order;
+        cond
+    }
+}
 
 pub struct Depot {
     pub queue: Mutex<PriorityQueue<CondStmt, QPriority>>,
@@ -21,60 +57,223 @@ pub struct Depot {
     pub num_hangs: AtomicUsize,
     pub num_crashes: AtomicUsize,
     pub dirs: DepotDir,
+    // The tainted conditions found in each input, with their offsets on that input.
+    pub(super) input_conds: Mutex<HashMap<usize, Vec<InputCond>>>,
+    pub(super) len_fields: Mutex<HashMap<usize, Vec<LenField>>>,
+    pub policy: QueuePolicy,
+    pub meta: Mutex<DepotMeta>,
+    // File names of the inputs in the queue.
+    pub(super) input_names: Mutex<HashMap<usize, String>>,
+    provenance_log: Mutex<Option<fs::File>>,
+    cache: Mutex<InputCache>,
+    config: Config,
 }
 
 impl Depot {
-    pub fn new(in_dir: PathBuf, out_dir: &Path) -> Self {
+    pub fn new(in_dir: PathBuf, out_dir: &Path, policy: QueuePolicy, config: &Config) -> Self {
+        // Append if we resume in the same directory.
+        let provenance_log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(out_dir.join(defs::PROVENANCE_FILE))
+            .map_err(|e| warn!("Could not create provenance file: {:?}", e))
+            .ok();
         Self {
             queue: Mutex::new(PriorityQueue::new()),
             num_inputs: AtomicUsize::new(0),
             num_hangs: AtomicUsize::new(0),
             num_crashes: AtomicUsize::new(0),
             dirs: DepotDir::new(in_dir, out_dir),
+            input_conds: Mutex::new(HashMap::new()),
+            len_fields: Mutex::new(HashMap::new()),
+            policy,
+            meta: Mutex::new(DepotMeta::default()),
+            input_names: Mutex::new(HashMap::new()),
+            provenance_log: Mutex::new(provenance_log),
+            cache: Mutex::new(InputCache::new(config.input_cache_size << 20)),
+            config: config.clone(),
         }
     }
 
     fn save_input(
-        status: &StatusType,
+        status: StatusType,
         buf: &Vec<u8>,
         num: &AtomicUsize,
-        cmpid: u32,
+        origin: &Origin,
         dir: &Path,
-    ) -> usize {
+    ) -> Provenance {
         let id = num.fetch_add(1, Ordering::Relaxed);
         trace!(
             "Find {} th new {:?} input by fuzzing {}.",
             id,
             status,
-            cmpid
+            origin.cmpid
         );
-        let new_path = get_file_name(dir, id);
+        let prov = Provenance::new(id, status, origin);
+        let new_path = dir.join(prov.file_name());
         let mut f = fs::File::create(new_path.as_path()).expect("Could not save new input file.");
         f.write_all(buf)
             .expect("Could not write seed buffer to file.");
         f.flush().expect("Could not flush file I/O.");
-        id
+        prov
     }
 
-    pub fn save(&self, status: StatusType, buf: &Vec<u8>, cmpid: u32) -> usize {
-        match status {
+    pub fn save(&self, status: StatusType, buf: &Vec<u8>, origin: &Origin) -> usize {
+        let prov = match status {
             StatusType::Normal => {
-                Self::save_input(&status, buf, &self.num_inputs, cmpid, &self.dirs.inputs_dir)
+                let prov =
+                    Self::save_input(status, buf, &self.num_inputs, origin, &self.dirs.inputs_dir);
+                self.lock_meta().add_input(prov.id, origin.parent, false);
+                self.lock_input_names().insert(prov.id, prov.file_name());
+                // It is likely to be fuzzed soon.
+                self.lock_cache().insert(prov.id, buf);
+                prov
             },
             StatusType::Timeout => {
-                Self::save_input(&status, buf, &self.num_hangs, cmpid, &self.dirs.hangs_dir)
+                Self::save_input(status, buf, &self.num_hangs, origin, &self.dirs.hangs_dir)
             },
-            StatusType::Crash => Self::save_input(
-                &status,
+            StatusType::Crash(_) => Self::save_input(
+                status,
                 buf,
                 &self.num_crashes,
-                cmpid,
+                origin,
                 &self.dirs.crashes_dir,
             ),
-            _ => 0,
+            _ => return 0,
+        };
+        self.log_provenance(&prov);
+        prov.id
+    }
+
+    // Next to the crash or hang.
+    pub fn save_output(&self, status: StatusType, id: usize, origin: &Origin, output: &[u8]) {
+        let dir = match status {
+            StatusType::Timeout => &self.dirs.hangs_dir,
+            StatusType::Crash(_) => &self.dirs.crashes_dir,
+            _ => return,
+        };
+        let name = Provenance::new(id, status, origin).file_name() + defs::OUTPUT_LOG_SUFFIX;
+        if let Err(e) = fs::write(dir.join(name), output) {
+            warn!("Could not save output: {:?}", e);
+        }
+    }
+
+    fn log_provenance(&self, prov: &Provenance) {
+        let mut log = match self.provenance_log.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(ref mut f) = *log {
+            let line = serde_json::to_string(prov).expect("Could not serialize!");
+            if let Err(e) = writeln!(f, "{}", line) {
+                warn!("Could not write provenance: {:?}", e);
+            }
+        }
+    }
+
+    pub(super) fn lock_meta(&self) -> MutexGuard<'_, DepotMeta> {
+        match self.meta.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    pub(super) fn lock_input_names(&self) -> MutexGuard<'_, HashMap<usize, String>> {
+        match self.input_names.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    pub(super) fn lock_queue(&self) -> MutexGuard<'_, PriorityQueue<CondStmt, QPriority>> {
+        match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
         }
     }
 
+    pub(super) fn lock_input_conds(&self) -> MutexGuard<'_, HashMap<usize, Vec<InputCond>>> {
+        match self.input_conds.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    pub(super) fn lock_len_fields(&self) -> MutexGuard<'_, HashMap<usize, Vec<LenField>>> {
+        match self.len_fields.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, InputCache> {
+        match self.cache.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    // (hits, misses, size in bytes)
+    pub fn cache_stats(&self) -> (usize, usize, usize) {
+        let cache = self.lock_cache();
+        (cache.num_hits, cache.num_misses, cache.size())
+    }
+
+    pub fn set_targets(&self, targets: Targets) {
+        self.lock_meta().set_targets(targets);
+    }
+
+    // The conditions of an input are tracked.
+    pub fn update_distance(&self, id: usize, conds: &[CondStmt]) {
+        let trace: Vec<u32> = conds.iter().map(|c| c.base.cmpid).collect();
+        let learned = self.lock_meta().update_distance(id, &trace);
+        if learned {
+            self.rescore_queue();
+        }
+    }
+
+    // The factors of the entries have changed, e.g. the distances.
+    fn rescore_queue(&self) {
+        // Take the queue first, the same order as the others.
+        let mut q = match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        let meta = self.lock_meta();
+        for (cond, p) in q.iter_mut() {
+            *p = p.rescore(self.policy, &meta.factors(cond));
+        }
+    }
+
+    // The input has found new edges.
+    pub fn set_favoured(&self, id: usize) {
+        let mut meta = self.lock_meta();
+        let mut m = meta.get_input(id);
+        m.favoured = true;
+        meta.inputs.insert(id, m);
+    }
+
     pub fn empty(&self) -> bool {
         self.num_inputs.load(Ordering::Relaxed) == 0
     }
@@ -84,8 +283,38 @@ impl Depot {
     }
 
     pub fn get_input_buf(&self, id: usize) -> Vec<u8> {
-        let path = get_file_name(&self.dirs.inputs_dir, id);
-        read_from_file(&path)
+        if let Some(buf) = self.lock_cache().get(id) {
+            return buf;
+        }
+        let path = match self.lock_input_names().get(&id) {
+            Some(name) => self.dirs.inputs_dir.join(name),
+            None => get_file_name(&self.dirs.inputs_dir, id),
+        };
+        let buf = read_from_file(&path);
+        self.lock_cache().insert(id, &buf);
+        buf
+    }
+
+    pub fn set_len_fields(&self, id: usize, fields: Vec<LenField>) {
+        let mut len_fields = match self.len_fields.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        len_fields.insert(id, fields);
+    }
+
+    pub fn get_len_fields(&self, id: usize) -> Vec<LenField> {
+        let len_fields = match self.len_fields.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        len_fields.get(&id).cloned().unwrap_or_default()
     }
 
     pub fn get_entry(&self) -> Option<(CondStmt, QPriority)> {
@@ -97,10 +326,11 @@ impl Depot {
             },
         };
         q.peek()
-            .and_then(|x| Some((x.0.clone(), x.1.clone())))
+            .and_then(|x| Some((x.0.clone(), *x.1)))
             .and_then(|x| {
                 if !x.1.is_done() {
-                    let q_inc = x.1.inc(x.0.base.op);
+                    let factors = self.lock_meta().factors(&x.0);
+                    let q_inc = x.1.inc(x.0.base.op, self.policy, &factors);
                     q.change_priority(&(x.0), q_inc);
                 }
                 Some(x)
@@ -117,8 +347,13 @@ impl Depot {
         };
 
         let mut ret: Vec<interesting_val::SCond> = Vec::new();
+        let mut tainted = vec![];
+        let mut meta = self.lock_meta();
 
         for mut cond in conds {
+            if cond.is_tainted() {
+                tainted.push((cond.base.belong as usize, InputCond::new(&cond)));
+            }
             if cond.is_desirable {
                 if let Some(v) = q.get_mut(&cond) {
                     if !v.0.is_done() {
@@ -128,25 +363,90 @@ impl Depot {
                             v.0.mark_as_done();
                             q.change_priority(&cond, QPriority::done());
                             ret.push(interesting_val::SCond::new(cond.base.cmpid, cond.offsets));
+                        } else if v.0.is_discarded() {
+                            // Reached by another input, which may have other offsets.
+                            if v.0.base.belong != cond.base.belong && v.0.can_revive(self.config.revive_max_times) {
+                                debug!("Revive cond {} from input {}", cond.base.cmpid, cond.base.belong);
+                                cond.num_revivals = v.0.num_revivals + 1;
+                                cond.num_exec = v.0.num_exec;
+                                mem::swap(v.0, &mut cond);
+                                let factors = meta.factors(&cond);
+                                let priority = QPriority::init(cond.base.op, self.policy, &factors);
+                                q.change_priority(&cond, priority);
+                            }
                         } else {
                             // Existed, but the new one are better
                             // If the cond is faster than the older one, we prefer the faster,
-                            if config::PREFER_FAST_COND && v.0.speed > cond.speed {
+                            if self.config.prefer_fast_cond && v.0.speed > cond.speed {
                                 mem::swap(v.0, &mut cond);
-                                let priority = QPriority::init(cond.base.op);
+                                let factors = meta.factors(&cond);
+                                let priority = QPriority::init(cond.base.op, self.policy, &factors);
                                 q.change_priority(&cond, priority);
                             }
                         }
                     }
                 } else {
-                    let priority = QPriority::init(cond.base.op);
+                    meta.add_cmpid(cond.base.cmpid);
+                    let factors = meta.factors(&cond);
+                    let priority = QPriority::init(cond.base.op, self.policy, &factors);
                     q.push(cond, priority);
                 }
             }
         }
+        drop(meta);
+        drop(q);
+
+        if !tainted.is_empty() {
+            let mut input_conds = match self.input_conds.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => {
+                    warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                    poisoned.into_inner()
+                },
+            };
+            for (belong, c) in tainted {
+                input_conds.entry(belong).or_insert_with(Vec::new).push(c);
+            }
+        }
         ret
     }
 
+    // Offsets of the input `id` referenced by pending conditions,
+    // and the ones referenced by solved conditions.
+    pub fn get_focus_offsets(&self, id: usize) -> (Vec<TagSeg>, Vec<TagSeg>) {
+        let input_conds = match self.input_conds.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        let conds = match input_conds.get(&id) {
+            Some(v) => v,
+            None => return (vec![], vec![]),
+        };
+
+        let q = match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        let mut open = vec![];
+        let mut solved = vec![];
+        for c in conds {
+            if let Some((cond, _)) = q.get(&c.key()) {
+                if cond.is_done() {
+                    solved.extend_from_slice(&c.offsets);
+                } else if !cond.is_discarded() {
+                    open.extend_from_slice(&c.offsets);
+                }
+            }
+        }
+        (open, solved)
+    }
+
     pub fn update_entry(&self, cond: CondStmt) {
         let mut q = match self.queue.lock() {
             Ok(guard) => guard,
@@ -157,11 +457,56 @@ impl Depot {
         };
         if let Some(v) = q.get_mut(&cond) {
             v.0.clone_from(&cond);
+            if v.0.is_discarded() && v.0.discarded_at == 0 {
+                v.0.discarded_at = now_secs();
+            }
         } else {
             warn!("Update entry: can not find this cond");
         }
         if cond.is_discarded() {
             q.change_priority(&cond, QPriority::done());
+        } else if let Some(p) = q.get_priority(&cond).cloned() {
+            // e.g. it has consumed more executions
+            let factors = self.lock_meta().factors(&cond);
+            q.change_priority(&cond, p.rescore(self.policy, &factors));
+        }
+    }
+
+    // Retry the conditions discarded `interval` seconds ago.
+    pub fn revive_expired(&self, interval: u64) -> usize {
+        let mut q = match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        let now = now_secs();
+        let expired: Vec<CondStmt> = q
+            .iter()
+            .filter(|(c, _)| {
+                c.can_revive(self.config.revive_max_times) && c.discarded_at > 0 && now >= c.discarded_at + interval
+            })
+            .map(|(c, _)| c.clone())
+            .collect();
+        let meta = self.lock_meta();
+        for c in &expired {
+            if let Some(v) = q.get_mut(c) {
+                v.0.revive();
+            }
+            if let Some((cond, _)) = q.get(c) {
+                let factors = meta.factors(cond);
+                let priority = QPriority::init(cond.base.op, self.policy, &factors);
+                q.change_priority(c, priority);
+            }
         }
+        expired.len()
     }
 }
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
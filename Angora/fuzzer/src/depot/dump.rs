@@ -10,10 +10,11 @@ impl Drop for Depot {
         let mut log_q = fs::File::create(dir.join(defs::COND_QUEUE_FILE)).unwrap();
         writeln!(
             log_q,
-            "cmpid, context, order, belong, p, op, condition, is_desirable, offsets, state"
+            "cmpid, context, order, belong, p, op, condition, arg1, arg2, is_desirable, offsets, state, rounds, speed, depth, cmpid_freq, num_exec, favoured"
         )
         .unwrap();
         let q = self.queue.lock().unwrap();
+        let meta = self.lock_meta();
 
         for (cond, p) in q.iter() {
             if !cond.base.is_afl() {
@@ -22,9 +23,10 @@ impl Drop for Depot {
                     offsets.push(format!("{}-{}", off.begin, off.end));
                 }
 
+                let factors = meta.factors(cond);
                 writeln!(
                     log_q,
-                    "{}, {}, {}, {}, {}, {}, {}, {:x}, {:x}, {}, {}, {:?}",
+                    "{}, {}, {}, {}, {}, {}, {}, {:x}, {:x}, {}, {}, {:?}, {}, {}, {}, {}, {}, {}",
                     cond.base.cmpid,
                     cond.base.context,
                     cond.base.order,
@@ -36,7 +38,13 @@ impl Drop for Depot {
                     cond.base.arg2,
                     cond.is_desirable,
                     offsets.join("&"),
-                    cond.state
+                    cond.state,
+                    p.rounds(),
+                    factors.speed,
+                    factors.depth,
+                    factors.cmpid_freq,
+                    factors.num_exec,
+                    factors.favoured
                 )
                 .unwrap();
             }
@@ -0,0 +1,119 @@
+// Information about the inputs and cmpids in the queue, used for the priority.
+use super::{qpriority::PriorityFactors, target::Targets};
+use crate::cond_stmt::CondStmt;
+use angora_common::config;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct InputMeta {
+    // How many generations it is from the seeds.
+    pub depth: u32,
+    // It found new edges, not only new hit counts.
+    pub favoured: bool,
+    // To the targets, if it is directed and the input has been tracked.
+    pub distance: Option<u32>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DepotMeta {
+    pub inputs: HashMap<usize, InputMeta>,
+    cmpid_freq: HashMap<u32, u32>,
+    targets: Targets,
+    // The distinct cmpids of each tracked input, if it is directed,
+    // to update its distance when the targets learn new ones.
+    traces: HashMap<usize, Vec<u32>>,
+}
+
+impl DepotMeta {
+    pub fn add_input(&mut self, id: usize, parent: Option<usize>, favoured: bool) {
+        let depth = match parent.and_then(|p| self.inputs.get(&p)) {
+            Some(m) => m.depth + 1,
+            None => 0,
+        };
+        self.inputs.insert(
+            id,
+            InputMeta {
+                depth,
+                favoured,
+                distance: None,
+            },
+        );
+    }
+
+    pub fn get_input(&self, id: usize) -> InputMeta {
+        self.inputs.get(&id).cloned().unwrap_or_default()
+    }
+
+    pub fn add_cmpid(&mut self, cmpid: u32) {
+        *self.cmpid_freq.entry(cmpid).or_insert(0) += 1;
+    }
+
+    // Keep the learned distances if we resume with the same targets.
+    pub fn set_targets(&mut self, targets: Targets) {
+        if !self.targets.same_cmpids(&targets) {
+            self.targets = targets;
+        }
+    }
+
+    // `trace` is the cmpids of the input in executed order.
+    // Return true if the distances of the inputs tracked before have changed.
+    pub fn update_distance(&mut self, id: usize, trace: &[u32]) -> bool {
+        if self.targets.is_empty() {
+            return false;
+        }
+        let learned = self.targets.update(trace);
+        let mut cmpids = trace.to_vec();
+        cmpids.sort_unstable();
+        cmpids.dedup();
+        let mut m = self.get_input(id);
+        m.distance = Some(self.targets.distance(&cmpids));
+        self.inputs.insert(id, m);
+        self.traces.insert(id, cmpids);
+        if learned {
+            for (i, cmpids) in &self.traces {
+                if let Some(m) = self.inputs.get_mut(i) {
+                    m.distance = Some(self.targets.distance(cmpids));
+                }
+            }
+        }
+        learned
+    }
+
+    pub fn factors(&self, cond: &CondStmt) -> PriorityFactors {
+        let input = self.get_input(cond.base.belong as usize);
+        PriorityFactors {
+            speed: cond.speed,
+            depth: input.depth,
+            cmpid_freq: self.cmpid_freq.get(&cond.base.cmpid).cloned().unwrap_or(0),
+            num_exec: cond.num_exec,
+            favoured: input.favoured,
+            distance: if self.targets.is_empty() {
+                None
+            } else {
+                Some(input.distance.unwrap_or(config::TARGET_FAR_DIST))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_distance() {
+        let mut meta = DepotMeta::default();
+        meta.set_targets(Targets::parse("9"));
+        meta.add_input(0, None, false);
+        meta.add_input(1, Some(0), false);
+        assert!(!meta.update_distance(0, &[1, 2, 2]));
+        let mut cond = CondStmt::new();
+        cond.base.belong = 0;
+        assert_eq!(meta.factors(&cond).distance, Some(config::TARGET_FAR_DIST));
+
+        // Input 0 is closer with what is learned from input 1.
+        assert!(meta.update_distance(1, &[2, 9]));
+        assert_eq!(meta.factors(&cond).distance, Some(1));
+    }
+}
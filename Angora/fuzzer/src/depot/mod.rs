@@ -1,9 +1,22 @@
+mod cache;
+mod checkpoint;
 mod depot;
 mod depot_dir;
 mod dump;
 mod file;
+mod meta;
+mod provenance;
 mod qpriority;
 mod sync;
+mod target;
 
-pub use self::{depot::Depot, file::*, sync::*};
-use self::{depot_dir::DepotDir, qpriority::QPriority};
+pub use self::{
+    checkpoint::DepotCheckpoint,
+    depot::Depot,
+    file::*,
+    provenance::Origin,
+    qpriority::{parse_queue_policy, QueuePolicy},
+    sync::*,
+    target::Targets,
+};
+use self::{cache::InputCache, depot_dir::DepotDir, meta::DepotMeta, provenance::Provenance, qpriority::QPriority};
@@ -0,0 +1,118 @@
+// Where each saved input comes from.
+use crate::{
+    executor::{CrashKind, StatusType},
+    fuzz_type::{get_fuzz_type_name, FuzzType},
+    search::SearchMethod,
+};
+use serde_derive::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Filled by the executor when it finds a new input.
+#[derive(Clone, Copy)]
+pub struct Origin {
+    // The input mutated from, None for the seeds.
+    pub parent: Option<usize>,
+    pub cmpid: u32,
+    pub context: u32,
+    pub fuzz_type: FuzzType,
+    pub search_method: SearchMethod,
+    // e.g. seed, det, gd, havoc, splice.
+    pub stage: &'static str,
+    pub num_exec: usize,
+}
+
+#[derive(Serialize)]
+pub struct Provenance {
+    pub id: usize,
+    pub status: String,
+    // What finds a crash, e.g. signal or asan.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crash: Option<&'static str>,
+    pub src: Option<usize>,
+    pub cmpid: u32,
+    pub context: u32,
+    pub fuzz_type: String,
+    pub search: String,
+    pub op: String,
+    // seconds since the epoch
+    pub time: u64,
+    pub num_exec: usize,
+}
+
+impl Provenance {
+    pub fn new(id: usize, status: StatusType, origin: &Origin) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            id,
+            status: match status {
+                StatusType::Crash(_) => "Crash".to_string(),
+                _ => format!("{:?}", status),
+            },
+            crash: match status {
+                StatusType::Crash(kind) => Some(kind.name()),
+                _ => None,
+            },
+            src: origin.parent,
+            cmpid: origin.cmpid,
+            context: origin.context,
+            fuzz_type: get_fuzz_type_name(origin.fuzz_type.index()),
+            search: origin.search_method.name().to_string(),
+            op: origin.stage.to_string(),
+            time,
+            num_exec: origin.num_exec,
+        }
+    }
+
+    // AFL style, e.g. id:000123,src:000045,op:gd,cmp:1234
+    pub fn file_name(&self) -> String {
+        let mut name = format!("id:{:06}", self.id);
+        if let Some(src) = self.src {
+            name.push_str(&format!(",src:{:06}", src));
+        }
+        name.push_str(&format!(",op:{}", self.op));
+        if self.cmpid > 0 {
+            name.push_str(&format!(",cmp:{}", self.cmpid));
+        }
+        match self.crash {
+            Some(kind) if kind != CrashKind::Signal.name() => {
+                name.push_str(&format!(",san:{}", kind));
+            },
+            _ => {},
+        }
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        let mut origin = Origin {
+            parent: Some(45),
+            cmpid: 1234,
+            context: 0,
+            fuzz_type: FuzzType::ExploreFuzz,
+            search_method: SearchMethod::Gd,
+            stage: "gd",
+            num_exec: 10,
+        };
+        let p = Provenance::new(123, StatusType::Normal, &origin);
+        assert_eq!(p.file_name(), "id:000123,src:000045,op:gd,cmp:1234");
+        assert_eq!(p.fuzz_type, "Explore");
+        origin.parent = None;
+        origin.cmpid = 0;
+        origin.stage = "seed";
+        let p = Provenance::new(0, StatusType::Normal, &origin);
+        assert_eq!(p.file_name(), "id:000000,op:seed");
+        let p = Provenance::new(1, StatusType::Crash(CrashKind::Ubsan), &origin);
+        assert_eq!(p.file_name(), "id:000001,op:seed,san:ubsan");
+        assert_eq!(p.status, "Crash");
+        let p = Provenance::new(2, StatusType::Crash(CrashKind::Signal), &origin);
+        assert_eq!(p.file_name(), "id:000002,op:seed");
+    }
+}
@@ -1,61 +1,146 @@
-use angora_common::defs;
+use angora_common::{config, defs};
+use serde_derive::{Deserialize, Serialize};
 use std::{self, cmp::Ordering, fmt};
 
 const INIT_PRIORITY: u16 = 0;
 const AFL_INIT_PRIORITY: u16 = 0;
 const DONE_PRIORITY: u16 = std::u16::MAX;
+const DONE_SCORE: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueuePolicy {
+    // Round-robin by how many times the entry was picked.
+    Legacy,
+    // Prefer fast conditions with rare cmpids, in deep and favoured inputs,
+    // that have not consumed many executions.
+    FastRare,
+}
+
+pub fn parse_queue_policy(p: &str) -> QueuePolicy {
+    match p {
+        "legacy" => QueuePolicy::Legacy,
+        "fast_rare" => QueuePolicy::FastRare,
+        _ => QueuePolicy::Legacy,
+    }
+}
+
+// What the score is computed from, besides the rounds.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PriorityFactors {
+    pub speed: u32,
+    pub depth: u32,
+    pub cmpid_freq: u32,
+    pub num_exec: usize,
+    pub favoured: bool,
+    // To the targets, None if it is not directed.
+    pub distance: Option<u32>,
+}
+
+impl QueuePolicy {
+    // Smaller is better.
+    pub fn score(&self, rounds: u16, f: &PriorityFactors) -> u32 {
+        let s = match self {
+            QueuePolicy::Legacy => rounds as u32,
+            QueuePolicy::FastRare => {
+                let mut s = rounds as f64 + 1.0;
+                s *= 1.0 + (f.speed as f64 + 1.0).log2() / 4.0;
+                s *= 1.0 + (f.cmpid_freq.max(1) as f64).log2();
+                s *= 1.0 + (f.num_exec as f64 / config::QUEUE_EXEC_UNIT as f64 + 1.0).log2();
+                s /= 1.0 + (f.depth as f64 + 1.0).log2() / 8.0;
+                if f.favoured {
+                    s /= 2.0;
+                }
+                (s * 16.0).min((DONE_SCORE - 1) as f64) as u32
+            },
+        };
+        // Directed: the farther from the targets, the later.
+        match f.distance {
+            Some(d) => (s as u64 + 1)
+                .saturating_mul(d as u64 + 1)
+                .min((DONE_SCORE - 1) as u64) as u32,
+            None => s,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct QPriority {
+    rounds: u16,
+    score: u32,
+}
 
-#[derive(Eq, PartialEq, Clone, Copy, Debug)]
-pub struct QPriority(u16);
 impl QPriority {
-    pub fn inc(&self, op: u32) -> Self {
-        if op == defs::COND_AFL_OP {
+    pub fn inc(&self, op: u32, policy: QueuePolicy, f: &PriorityFactors) -> Self {
+        let rounds = if op == defs::COND_AFL_OP {
             self.afl_inc()
         } else {
             self.base_inc()
-        }
+        };
+        Self::new(rounds, policy, f)
     }
-    fn base_inc(&self) -> Self {
-        QPriority(self.0 + 1)
+
+    fn base_inc(&self) -> u16 {
+        self.rounds + 1
     }
 
-    fn afl_inc(&self) -> Self {
-        QPriority(self.0 + 2)
+    fn afl_inc(&self) -> u16 {
+        self.rounds + 2
     }
 
-    pub fn init(op: u32) -> Self {
-        if op == defs::COND_AFL_OP {
-            Self::afl_init()
+    pub fn init(op: u32, policy: QueuePolicy, f: &PriorityFactors) -> Self {
+        let rounds = if op == defs::COND_AFL_OP {
+            AFL_INIT_PRIORITY
         } else {
-            Self::base_init()
-        }
+            INIT_PRIORITY
+        };
+        Self::new(rounds, policy, f)
     }
 
-    fn base_init() -> Self {
-        QPriority(INIT_PRIORITY)
+    fn new(rounds: u16, policy: QueuePolicy, f: &PriorityFactors) -> Self {
+        // Leave DONE_PRIORITY for the done ones.
+        let rounds = std::cmp::min(rounds, DONE_PRIORITY - 1);
+        Self {
+            rounds,
+            score: policy.score(rounds, f),
+        }
     }
 
-    fn afl_init() -> Self {
-        QPriority(AFL_INIT_PRIORITY)
+    // Re-compute the score if the factors have changed.
+    pub fn rescore(&self, policy: QueuePolicy, f: &PriorityFactors) -> Self {
+        if self.is_done() {
+            *self
+        } else {
+            Self::new(self.rounds, policy, f)
+        }
     }
 
     pub fn done() -> Self {
-        QPriority(DONE_PRIORITY)
+        Self {
+            rounds: DONE_PRIORITY,
+            score: DONE_SCORE,
+        }
     }
 
     pub fn is_done(&self) -> bool {
-        self.0 == DONE_PRIORITY
+        self.rounds == DONE_PRIORITY
+    }
+
+    pub fn rounds(&self) -> u16 {
+        self.rounds
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
     }
 }
 
 // Make the queue get smallest priority first.
 impl Ord for QPriority {
     fn cmp(&self, other: &QPriority) -> Ordering {
-        match self.0.cmp(&other.0) {
-            Ordering::Greater => Ordering::Less,
-            Ordering::Less => Ordering::Greater,
-            Ordering::Equal => Ordering::Equal,
-        }
+        other
+            .score
+            .cmp(&self.score)
+            .then(other.rounds.cmp(&self.rounds))
     }
 }
 
@@ -67,6 +152,75 @@ impl PartialOrd for QPriority {
 
 impl fmt::Display for QPriority {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_order() {
+        let f = PriorityFactors::default();
+        let p0 = QPriority::init(0, QueuePolicy::Legacy, &f);
+        let p1 = p0.inc(0, QueuePolicy::Legacy, &f);
+        let afl = QPriority::init(defs::COND_AFL_OP, QueuePolicy::Legacy, &f)
+            .inc(defs::COND_AFL_OP, QueuePolicy::Legacy, &f);
+        assert!(p0 > p1);
+        assert_eq!(afl.rounds(), 2);
+        assert!(p1 > afl);
+        assert!(afl > QPriority::done());
+    }
+
+    #[test]
+    fn test_fast_rare_order() {
+        let policy = QueuePolicy::FastRare;
+        let base = PriorityFactors {
+            speed: 100,
+            depth: 1,
+            cmpid_freq: 2,
+            num_exec: 0,
+            favoured: false,
+            distance: None,
+        };
+        let slow = PriorityFactors {
+            speed: 10000,
+            ..base
+        };
+        let common = PriorityFactors {
+            cmpid_freq: 64,
+            ..base
+        };
+        let favoured = PriorityFactors {
+            favoured: true,
+            ..base
+        };
+        let p = QPriority::init(0, policy, &base);
+        assert!(p > QPriority::init(0, policy, &slow));
+        assert!(p > QPriority::init(0, policy, &common));
+        assert!(p < QPriority::init(0, policy, &favoured));
+        assert!(p > p.inc(0, policy, &base));
+    }
+
+    #[test]
+    fn test_directed_order() {
+        let near = PriorityFactors {
+            distance: Some(0),
+            ..Default::default()
+        };
+        let far = PriorityFactors {
+            distance: Some(config::TARGET_FAR_DIST),
+            ..near
+        };
+        for &policy in &[QueuePolicy::Legacy, QueuePolicy::FastRare] {
+            let p = QPriority::init(0, policy, &far);
+            // Picked many times, but still before the far one.
+            let mut q = QPriority::init(0, policy, &near);
+            for _ in 0..8 {
+                q = q.inc(0, policy, &near);
+            }
+            assert!(q > p);
+        }
     }
 }
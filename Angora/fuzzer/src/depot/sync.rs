@@ -1,6 +1,6 @@
 use super::*;
-use crate::executor::Executor;
-use angora_common::{config, defs};
+use crate::{executor::Executor, multi_input};
+use angora_common::defs;
 use std::{
     collections::HashMap,
     fs,
@@ -13,6 +13,7 @@ use std::{
 
 pub fn sync_depot(executor: &mut Executor, running: Arc<AtomicBool>, dir: &Path) {
     executor.local_stats.clear();
+    executor.stage = "seed";
     let seed_dir = dir.read_dir().expect("read_dir call failed");
     for entry in seed_dir {
         if let Ok(entry) = entry {
@@ -23,12 +24,20 @@ pub fn sync_depot(executor: &mut Executor, running: Arc<AtomicBool>, dir: &Path)
             if path.is_file() {
                 let file_len =
                     fs::metadata(path).expect("Could not fetch metadata.").len() as usize;
-                if file_len < config::MAX_INPUT_LEN {
+                if file_len < executor.cmd.config.max_input_len {
                     let buf = read_from_file(path);
                     executor.run_sync(&buf);
                 } else {
                     warn!("Seed discarded, too long: {:?}", path);
                 }
+            } else if path.is_dir() && executor.cmd.num_parts > 0 {
+                match multi_input::pack_dir(path, executor.cmd.num_parts) {
+                    Ok(ref buf) if buf.len() < executor.cmd.config.max_input_len => {
+                        executor.run_sync(buf);
+                    },
+                    Ok(_) => warn!("Seed discarded, too long: {:?}", path),
+                    Err(e) => warn!("Could not read the seed {:?}: {:?}", path, e),
+                }
             }
         }
     }
@@ -43,8 +52,9 @@ pub fn sync_afl(
     sync_dir: &Path,
     sync_ids: &mut HashMap<String, usize>,
 ) {
-    executor.rebind_forksrv();
+    executor.restart_backend();
     executor.local_stats.clear();
+    executor.stage = "sync";
 
     if let Ok(entries) = sync_dir.read_dir() {
         for entry in entries {
@@ -106,7 +116,7 @@ fn sync_one_afl_dir(
                 if let Some(id) = get_afl_id(&entry) {
                     if id >= min_id {
                         let file_len = fs::metadata(path).unwrap().len() as usize;
-                        if file_len < config::MAX_INPUT_LEN {
+                        if file_len < executor.cmd.config.max_input_len {
                             let buf = read_from_file(path);
                             executor.run_sync(&buf);
                         }
@@ -0,0 +1,118 @@
+// Directed fuzzing: how close the inputs come to the target conditions,
+// measured on the recorded condition sequences since we have no CFG here.
+use angora_common::config;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Targets {
+    cmpids: HashSet<u32>,
+    // The fewest conditions seen between a cmpid and a target after it.
+    dist: HashMap<u32, u32>,
+}
+
+impl Targets {
+    // Either a cmpid per line, or the lines of the compiling log with
+    // `ANGORA_OUTPUT_COND_LOC=1` for the targets, e.g.
+    // [ID] 1234
+    // [INS] %cmp = icmp eq i32 %x, 42
+    // [LOC] header.c, Ln 42, Col 3
+    pub fn parse(content: &str) -> Self {
+        let mut cmpids = HashSet::new();
+        for line in content.lines() {
+            let line = line.trim();
+            let id = line.strip_prefix("[ID]").unwrap_or(line);
+            if let Ok(id) = id.trim().parse::<u32>() {
+                cmpids.insert(id);
+            }
+        }
+        Self {
+            cmpids,
+            dist: HashMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Self {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Could not read target file {:?}: {:?}", path, e);
+                panic!();
+            },
+        };
+        let targets = Self::parse(&content);
+        if targets.is_empty() {
+            error!("No target cmpid in {:?}", path);
+            panic!();
+        }
+        info!("Load {} targets from {:?}", targets.cmpids.len(), path);
+        targets
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cmpids.is_empty()
+    }
+
+    pub fn same_cmpids(&self, other: &Self) -> bool {
+        self.cmpids == other.cmpids
+    }
+
+    // Learn the distances from a trace, the cmpids in executed order.
+    // Return true if any distance is new or shorter.
+    pub fn update(&mut self, trace: &[u32]) -> bool {
+        let mut learned = false;
+        let mut next_target = None;
+        for (i, cmpid) in trace.iter().enumerate().rev() {
+            if self.cmpids.contains(cmpid) {
+                next_target = Some(i);
+            }
+            if let Some(j) = next_target {
+                let d = (j - i) as u32;
+                let e = self.dist.entry(*cmpid).or_insert(u32::MAX);
+                if d < *e {
+                    *e = d;
+                    learned = true;
+                }
+            }
+        }
+        learned
+    }
+
+    // The distance of a trace is the one of its closest condition,
+    // and the ones never seen before a target are far.
+    pub fn distance(&self, trace: &[u32]) -> u32 {
+        trace
+            .iter()
+            .filter_map(|c| self.dist.get(c))
+            .min()
+            .cloned()
+            .unwrap_or(config::TARGET_FAR_DIST)
+            .min(config::TARGET_FAR_DIST)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        let mut t = Targets::parse("[ID] 7\n[INS] icmp\n[LOC] a.c, Ln 3, Col 1\n9\n# a comment\n");
+        assert!(t.same_cmpids(&Targets::parse("9\n7")));
+        assert_eq!(t.distance(&[1, 2]), config::TARGET_FAR_DIST);
+
+        assert!(!t.update(&[1, 2]));
+        assert!(t.update(&[1, 2, 3, 7, 4, 2, 9]));
+        assert!(!t.update(&[4, 2, 9]));
+        assert_eq!(t.distance(&[7]), 0);
+        assert_eq!(t.distance(&[1, 5]), 3);
+        // 2 is closer to 9 in the end.
+        assert_eq!(t.distance(&[2]), 1);
+        assert_eq!(t.distance(&[4]), 2);
+        assert_eq!(t.distance(&[5, 6]), config::TARGET_FAR_DIST);
+    }
+}
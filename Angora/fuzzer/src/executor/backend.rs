@@ -0,0 +1,17 @@
+// Where the inputs are executed, and where their feedback comes from.
+use super::{ProcessBackend, StatusType};
+use crate::branches::BranchBuf;
+use angora_common::cond_stmt_base::CondStmtBase;
+
+pub trait ExecBackend {
+    fn write_input(&mut self, buf: &[u8]);
+    // Run the last written input. The branch trace is cleared, and the
+    // condition to solve is set in `cond`, before each run.
+    fn run(&mut self, trace: &mut BranchBuf, cond: &mut CondStmtBase) -> StatusType;
+    // After an error, e.g. the fork server is dead.
+    fn restart(&mut self) {}
+    // Tracking, sanitizer reports and memory limits need the real programs.
+    fn as_process(&mut self) -> Option<&mut ProcessBackend> {
+        None
+    }
+}
@@ -1,34 +1,39 @@
-use super::{limit::SetLimit, *};
+use super::*;
 
 use crate::{
     branches, command,
     cond_stmt::{self, NextState},
-    depot, stats, track,
+    depot, mut_input, mutator, stats, track,
     search::interesting_val,
     search,
 };
-use angora_common::{config, defs};
+use angora_common::{config, defs, tag::TagSeg};
 
 use std::{
-    collections::HashMap,
+    collections::VecDeque,
     path::Path,
-    process::{Command, Stdio},
     sync::{
         atomic::{compiler_fence, Ordering},
         Arc, RwLock,
     },
     time,
 };
-use wait_timeout::ChildExt;
+
+// A new crash or hang, re-run later to keep its output.
+struct PendingOutput {
+    buf: Vec<u8>,
+    status: StatusType,
+    id: usize,
+    origin: depot::Origin,
+    mem_limit: u64,
+}
 
 pub struct Executor {
     pub cmd: command::CommandOpt,
     pub branches: branches::Branches,
     pub t_conds: cond_stmt::ShmConds,
-    envs: HashMap<String, String>,
-    forksrv: Option<Forksrv>,
+    backend: Box<dyn ExecBackend>,
     depot: Arc<depot::Depot>,
-    fd: PipeFd,
     tmout_cnt: usize,
     invariable_cnt: usize,
     pub last_f: u64,
@@ -36,6 +41,10 @@ pub struct Executor {
     pub global_stats: Arc<RwLock<stats::ChartStats>>,
     pub local_stats: stats::LocalStats,
     pub dictionary: Arc<RwLock<search::interesting_val::Dict>>,
+    pub mutator: Option<Box<dyn mutator::CustomMutator>>,
+    // What is running now, recorded in the provenance of new inputs.
+    pub stage: &'static str,
+    pending_outputs: VecDeque<PendingOutput>,
 }
 
 impl Executor {
@@ -50,49 +59,64 @@ impl Executor {
         let branches = branches::Branches::new(global_branches);
         let t_conds = cond_stmt::ShmConds::new();
 
-        // ** Envs **
-        let mut envs = HashMap::new();
-        envs.insert(
-            defs::ASAN_OPTIONS_VAR.to_string(),
-            defs::ASAN_OPTIONS_CONTENT.to_string(),
-        );
-        envs.insert(
-            defs::MSAN_OPTIONS_VAR.to_string(),
-            defs::MSAN_OPTIONS_CONTENT.to_string(),
-        );
-        envs.insert(
-            defs::BRANCHES_SHM_ENV_VAR.to_string(),
-            branches.get_id().to_string(),
-        );
-        envs.insert(
-            defs::COND_STMT_ENV_VAR.to_string(),
-            t_conds.get_id().to_string(),
-        );
-        envs.insert(
-            defs::LD_LIBRARY_PATH_VAR.to_string(),
-            cmd.ld_library.clone(),
-        );
+        let backend = ProcessBackend::new(&cmd, branches.get_id(), t_conds.get_id());
+        Self::with_backend(
+            cmd,
+            branches,
+            t_conds,
+            Box::new(backend),
+            depot,
+            global_stats,
+            dictionary,
+        )
+    }
+
+    // Run `target` in the fuzzer instead of the programs, see InProcessBackend.
+    pub fn new_in_process<F>(
+        cmd: command::CommandOpt,
+        global_branches: Arc<branches::GlobalBranches>,
+        depot: Arc<depot::Depot>,
+        global_stats: Arc<RwLock<stats::ChartStats>>,
+        dictionary: Arc<RwLock<search::interesting_val::Dict>>,
+        target: F,
+    ) -> Self
+    where
+        F: FnMut(&[u8], &mut Feedback) -> StatusType + 'static,
+    {
+        let branches = branches::Branches::new(global_branches);
+        let t_conds = cond_stmt::ShmConds::new();
+        Self::with_backend(
+            cmd,
+            branches,
+            t_conds,
+            Box::new(InProcessBackend::new(target)),
+            depot,
+            global_stats,
+            dictionary,
+        )
+    }
 
-        let fd = pipe_fd::PipeFd::new(&cmd.out_file);
-        let forksrv = Some(forksrv::Forksrv::new(
-            &cmd.forksrv_socket_path,
-            &cmd.main,
-            &envs,
-            fd.as_raw_fd(),
-            cmd.is_stdin,
-            cmd.uses_asan,
-            cmd.time_limit,
-            cmd.mem_limit,
-        ));
+    fn with_backend(
+        cmd: command::CommandOpt,
+        branches: branches::Branches,
+        t_conds: cond_stmt::ShmConds,
+        backend: Box<dyn ExecBackend>,
+        depot: Arc<depot::Depot>,
+        global_stats: Arc<RwLock<stats::ChartStats>>,
+        dictionary: Arc<RwLock<search::interesting_val::Dict>>,
+    ) -> Self {
+        // Each executor has its own instance of the custom mutator.
+        let mutator = cmd
+            .custom_mutator
+            .as_ref()
+            .map(|path| mutator::load_custom_mutator(path, rand::random::<u32>()));
 
         Self {
             cmd,
             branches,
             t_conds,
-            envs,
-            forksrv,
+            backend,
             depot,
-            fd,
             tmout_cnt: 0,
             invariable_cnt: 0,
             last_f: defs::UNREACHABLE,
@@ -100,25 +124,14 @@ impl Executor {
             global_stats,
             local_stats: Default::default(),
             dictionary,
+            mutator,
+            stage: "seed",
+            pending_outputs: VecDeque::new(),
         }
     }
 
-    pub fn rebind_forksrv(&mut self) {
-        {
-            // delete the old forksrv
-            self.forksrv = None;
-        }
-        let fs = forksrv::Forksrv::new(
-            &self.cmd.forksrv_socket_path,
-            &self.cmd.main,
-            &self.envs,
-            self.fd.as_raw_fd(),
-            self.cmd.is_stdin,
-            self.cmd.uses_asan,
-            self.cmd.time_limit,
-            self.cmd.mem_limit,
-        );
-        self.forksrv = Some(fs);
+    pub fn restart_backend(&mut self) {
+        self.backend.restart();
     }
 
     // FIXME: The location id may be inconsistent between track and fast programs.
@@ -137,7 +150,7 @@ impl Executor {
         let mut skip = false;
         if output == self.last_f {
             self.invariable_cnt += 1;
-            if self.invariable_cnt >= config::MAX_INVARIABLE_NUM {
+            if self.invariable_cnt >= self.cmd.config.max_invariable_num {
                 debug!("output is invariable! f: {}", output);
                 if cond.is_desirable {
                     cond.is_desirable = false;
@@ -190,7 +203,7 @@ impl Executor {
         skip |= self.check_invariable(output, cond);
         self.check_consistent(output, cond);
 
-        let ret: Vec<interesting_val::SCond> = self.do_if_has_new(buf, status, explored, cond.base.cmpid);
+        let ret: Vec<interesting_val::SCond> = self.do_if_has_new(buf, status, explored, Some(cond));
         status = self.check_timeout(status, cond);
 
         if skip {
@@ -200,15 +213,20 @@ impl Executor {
         (status, output, ret)
     }
 
-    fn try_unlimited_memory(&mut self, buf: &Vec<u8>, cmpid: u32) -> bool {
+    fn try_unlimited_memory(&mut self, buf: &Vec<u8>, origin: &depot::Origin) -> bool {
         let mut skip = false;
+        let (main, mem_limit, time_limit) = (
+            self.cmd.main.clone(),
+            self.cmd.config.mem_limit_track,
+            self.cmd.time_limit,
+        );
+        let process = match self.backend.as_process() {
+            Some(p) => p,
+            None => return false,
+        };
         self.branches.clear_trace();
-        if self.cmd.is_stdin {
-            self.fd.rewind();
-        }
         compiler_fence(Ordering::SeqCst);
-        let unmem_status =
-            self.run_target(&self.cmd.main, config::MEM_LIMIT_TRACK, self.cmd.time_limit);
+        let unmem_status = process.run_target(&main, mem_limit, time_limit);
         compiler_fence(Ordering::SeqCst);
 
         // find difference
@@ -220,13 +238,14 @@ impl Executor {
             );
             // crash or hang
             if self.branches.has_new(unmem_status).0 {
-                self.depot.save(unmem_status, &buf, cmpid);
+                let id = self.depot.save(unmem_status, &buf, origin);
+                self.save_output(buf, unmem_status, id, origin, self.cmd.config.mem_limit_track);
             }
         }
         skip
     }
 
-    fn do_if_has_new(&mut self, buf: &Vec<u8>, status: StatusType, _explored: bool, cmpid: u32) -> Vec<interesting_val::SCond> {
+    fn do_if_has_new(&mut self, buf: &Vec<u8>, status: StatusType, _explored: bool, cond: Option<&cond_stmt::CondStmt>) -> Vec<interesting_val::SCond> {
         // new edge: one byte in bitmap
         let (has_new_path, has_new_edge, edge_num) = self.branches.has_new(status);
         let mut ret: Vec<interesting_val::SCond> = Default::default();
@@ -234,9 +253,33 @@ impl Executor {
         if has_new_path {
             self.has_new_path = true;
             self.local_stats.find_new(&status);
-            let id = self.depot.save(status, &buf, cmpid);
+            // Only built for the new inputs, since it takes the lock of the stats.
+            let origin = &self.origin(cond);
+            let id = self.depot.save(status, &buf, origin);
+            if status.is_crash() || status == StatusType::Timeout {
+                self.save_output(buf, status, id, origin, self.cmd.mem_limit);
+            }
+            if status == StatusType::Normal && self.cmd.is_persistent {
+                if let Some(leak_status) = self.check_leak() {
+                    warn!(
+                        "Input {} ends with {:?} or takes another path in the next persistent iteration, some state leaks between iterations! Restart the program.",
+                        id, leak_status
+                    );
+                    // The crash or hang is the state's, but it is still one.
+                    if (leak_status.is_crash() || leak_status == StatusType::Timeout)
+                        && self.branches.has_new(leak_status).0
+                    {
+                        let leak_id = self.depot.save(leak_status, buf, origin);
+                        self.save_output(buf, leak_status, leak_id, origin, self.cmd.mem_limit);
+                    }
+                    self.restart_backend();
+                }
+            }
 
             if status == StatusType::Normal {
+                if has_new_edge {
+                    self.depot.set_favoured(id);
+                }
                 self.local_stats.avg_edge_num.update(edge_num as f32);
                 let speed = self.count_time();
                 let speed_ratio = self.local_stats.avg_exec_time.get_ratio(speed as f32);
@@ -250,10 +293,11 @@ impl Executor {
                     );
                     return ret;
                 }
-                let crash_or_tmout = self.try_unlimited_memory(buf, cmpid);
+                let crash_or_tmout = self.try_unlimited_memory(buf, origin);
                 if !crash_or_tmout {
                     let cond_stmts = self.track(id, buf, speed);
                     if cond_stmts.len() > 0 {
+                        self.depot.update_distance(id, &cond_stmts);
                         ret = self.depot.add_entries(cond_stmts);
                         if self.cmd.enable_afl {
                             self.depot
@@ -268,10 +312,25 @@ impl Executor {
         ret
     }
 
+    // In persistent mode, the same input should take the same path
+    // in the next iteration of the same process. The status of the next one if not.
+    fn check_leak(&mut self) -> Option<StatusType> {
+        let path = self.branches.get_path();
+        self.branches.clear_trace();
+        compiler_fence(Ordering::SeqCst);
+        let status = self.backend.run(self.branches.trace_mut(), &mut self.t_conds.cond);
+        compiler_fence(Ordering::SeqCst);
+        if status != StatusType::Normal || self.branches.get_path() != path {
+            Some(status)
+        } else {
+            None
+        }
+    }
+
     pub fn run(&mut self, buf: &Vec<u8>, cond: &mut cond_stmt::CondStmt) -> (StatusType, Vec<interesting_val::SCond>) {
         self.run_init();
         let mut status = self.run_inner(buf);
-        let ret: Vec<interesting_val::SCond> = self.do_if_has_new(buf, status, false, 0);
+        let ret: Vec<interesting_val::SCond> = self.do_if_has_new(buf, status, false, Some(cond));
         status = self.check_timeout(status, cond);
         (status, ret)
     }
@@ -279,7 +338,21 @@ impl Executor {
     pub fn run_sync(&mut self, buf: &Vec<u8>) {
         self.run_init();
         let status = self.run_inner(buf);
-        self.do_if_has_new(buf, status, false, 0);
+        self.do_if_has_new(buf, status, false, None);
+    }
+
+    fn origin(&self, cond: Option<&cond_stmt::CondStmt>) -> depot::Origin {
+        let num_exec = self.global_stats.read().unwrap().get_num_exec()
+            + self.local_stats.num_exec.0;
+        depot::Origin {
+            parent: cond.map(|c| c.base.belong as usize),
+            cmpid: cond.map_or(0, |c| c.base.cmpid),
+            context: cond.map_or(0, |c| c.base.context),
+            fuzz_type: cond.map_or(Default::default(), |c| c.get_fuzz_type()),
+            search_method: self.cmd.search_method,
+            stage: self.stage,
+            num_exec,
+        }
     }
 
     fn run_init(&mut self) {
@@ -290,13 +363,13 @@ impl Executor {
     fn check_timeout(&mut self, status: StatusType, cond: &mut cond_stmt::CondStmt) -> StatusType {
         let mut ret_status = status;
         if ret_status == StatusType::Error {
-            self.rebind_forksrv();
+            self.restart_backend();
             ret_status = StatusType::Timeout;
         }
 
         if ret_status == StatusType::Timeout {
             self.tmout_cnt = self.tmout_cnt + 1;
-            if self.tmout_cnt >= config::TMOUT_SKIP {
+            if self.tmout_cnt >= self.cmd.config.tmout_skip {
                 cond.to_timeout();
                 ret_status = StatusType::Skip;
                 self.tmout_cnt = 0;
@@ -314,11 +387,7 @@ impl Executor {
         self.branches.clear_trace();
 
         compiler_fence(Ordering::SeqCst);
-        let ret_status = if let Some(ref mut fs) = self.forksrv {
-            fs.run()
-        } else {
-            self.run_target(&self.cmd.main, self.cmd.mem_limit, self.cmd.time_limit)
-        };
+        let ret_status = self.backend.run(self.branches.trace_mut(), &mut self.t_conds.cond);
         compiler_fence(Ordering::SeqCst);
 
         ret_status
@@ -327,17 +396,10 @@ impl Executor {
     fn count_time(&mut self) -> u32 {
         let t_start = time::Instant::now();
         for _ in 0..3 {
-            if self.cmd.is_stdin {
-                self.fd.rewind();
-            }
-            if let Some(ref mut fs) = self.forksrv {
-                let status = fs.run();
-                if status == StatusType::Error {
-                    self.rebind_forksrv();
-                    return defs::SLOW_SPEED;
-                }
-            } else {
-                self.run_target(&self.cmd.main, self.cmd.mem_limit, self.cmd.time_limit);
+            let status = self.backend.run(self.branches.trace_mut(), &mut self.t_conds.cond);
+            if status == StatusType::Error {
+                self.restart_backend();
+                return defs::SLOW_SPEED;
             }
         }
         let used_t = t_start.elapsed();
@@ -345,23 +407,25 @@ impl Executor {
         used_us / 3
     }
 
-    fn track(&mut self, id: usize, buf: &Vec<u8>, speed: u32) -> Vec<cond_stmt::CondStmt> {
-        self.envs.insert(
-            defs::TRACK_OUTPUT_VAR.to_string(),
-            self.cmd.track_path.clone(),
-        );
-
+    fn track(&mut self, id: usize, buf: &[u8], speed: u32) -> Vec<cond_stmt::CondStmt> {
         let t_now: stats::TimeIns = Default::default();
 
-        self.write_test(buf);
-
-        compiler_fence(Ordering::SeqCst);
-        let ret_status = self.run_target(
-            &self.cmd.track,
-            config::MEM_LIMIT_TRACK,
+        let (track, mem_limit, time_limit) = (
+            self.cmd.track.clone(),
+            self.cmd.config.mem_limit_track,
             //self.cmd.time_limit *
-            config::TIME_LIMIT_TRACK,
+            self.cmd.config.time_limit_track,
         );
+        let process = match self.backend.as_process() {
+            Some(p) => p,
+            None => return vec![],
+        };
+        // The track program always reads the file. It is not post-processed,
+        // since the offsets of the conditions are the ones in `buf`.
+        process.write(buf, false);
+
+        compiler_fence(Ordering::SeqCst);
+        let ret_status = process.run_target(&track, mem_limit, time_limit);
         compiler_fence(Ordering::SeqCst);
 
         if ret_status != StatusType::Normal {
@@ -372,74 +436,100 @@ impl Executor {
             return vec![];
         }
 
-        let cond_list = track::load_track_data(
+        let (cond_list, len_fields) = track::load_track_data(
             Path::new(&self.cmd.track_path),
             id as u32,
             speed,
             self.cmd.mode.is_pin_mode(),
             self.cmd.enable_exploitation,
+            buf,
         );
+        if !len_fields.is_empty() {
+            debug!("len fields of input {}: {:?}", id, len_fields);
+            self.depot.set_len_fields(id, len_fields);
+        }
 
         self.local_stats.track_time += t_now.into();
         cond_list
     }
 
+    pub fn get_focus_offsets(&self, id: usize) -> (Vec<TagSeg>, Vec<TagSeg>) {
+        self.depot.get_focus_offsets(id)
+    }
+
+    pub fn get_len_fields(&self, id: usize) -> Vec<mut_input::LenField> {
+        self.depot.get_len_fields(id)
+    }
+
     pub fn random_input_buf(&self) -> Vec<u8> {
         let id = self.depot.next_random();
         self.depot.get_input_buf(id)
     }
 
     fn write_test(&mut self, buf: &Vec<u8>) {
-        self.fd.write_buf(buf);
-        if self.cmd.is_stdin {
-            self.fd.rewind();
-        }
+        let max_len = self.cmd.config.max_input_len;
+        let new_buf = self.mutator.as_mut().and_then(|m| m.post_process(buf)).map(|mut b| {
+            b.truncate(max_len);
+            b
+        });
+        self.backend.write_input(new_buf.as_ref().unwrap_or(buf));
     }
 
-    fn run_target(
-        &self,
-        target: &(String, Vec<String>),
+    // Run the program with symbolized sanitizers, and get its report in stderr.
+    pub fn run_with_report(&mut self, buf: &Vec<u8>) -> (StatusType, String) {
+        let envs = CrashKind::options_envs(true);
+        // Symbolizing takes time.
+        let (mem_limit, time_limit) = (self.cmd.mem_limit, self.cmd.config.time_limit_track);
+        self.write_test(buf);
+        let (status, report) = match self.backend.as_process() {
+            Some(p) => p.run_with_output(&envs, false, mem_limit, time_limit),
+            None => (StatusType::Error, vec![]),
+        };
+        (status, String::from_utf8_lossy(&report).into_owned())
+    }
+
+    // Re-run a new crash or hang to keep what it prints, e.g. the ASAN report
+    // or the assertion message, next to it. It is queued, since a hang takes
+    // the whole time limit, and re-run by save_pending_outputs.
+    fn save_output(
+        &mut self,
+        buf: &[u8],
+        status: StatusType,
+        id: usize,
+        origin: &depot::Origin,
         mem_limit: u64,
-        time_limit: u64,
-    ) -> StatusType {
-        let mut cmd = Command::new(&target.0);
-        let mut child = cmd
-            .args(&target.1)
-            .stdin(Stdio::null())
-            .env_clear()
-            .envs(&self.envs)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .mem_limit(mem_limit.clone())
-            .setsid()
-            .pipe_stdin(self.fd.as_raw_fd(), self.cmd.is_stdin)
-            .spawn()
-            .expect("Could not run target");
-
-        let timeout = time::Duration::from_secs(time_limit);
-        let ret = match child.wait_timeout(timeout).unwrap() {
-            Some(status) => {
-                if let Some(status_code) = status.code() {
-                    if (self.cmd.uses_asan && status_code == defs::MSAN_ERROR_CODE)
-                        || (self.cmd.mode.is_pin_mode() && status_code > 128)
-                    {
-                        StatusType::Crash
-                    } else {
-                        StatusType::Normal
-                    }
-                } else {
-                    StatusType::Crash
-                }
-            }
-            None => {
-                // Timeout
-                // child hasn't exited yet
-                child.kill().expect("Could not send kill signal to child.");
-                child.wait().expect("Error during waiting for child.");
-                StatusType::Timeout
+    ) {
+        if self.backend.as_process().is_none() {
+            return;
+        }
+        if self.pending_outputs.len() >= config::OUTPUT_QUEUE_SIZE {
+            debug!("Too many pending outputs, skip the one of {:?} {}", status, id);
+            return;
+        }
+        self.pending_outputs.push_back(PendingOutput {
+            buf: buf.to_vec(),
+            status,
+            id,
+            origin: *origin,
+            mem_limit,
+        });
+    }
+
+    // Between the fuzzing rounds, for at most OUTPUT_RERUN_TIME seconds.
+    pub fn save_pending_outputs(&mut self) {
+        let t_start = time::Instant::now();
+        let time_limit = self.cmd.time_limit;
+        while t_start.elapsed().as_secs() < config::OUTPUT_RERUN_TIME {
+            let o = match self.pending_outputs.pop_front() {
+                Some(o) => o,
+                None => break,
+            };
+            self.write_test(&o.buf);
+            if let Some(p) = self.backend.as_process() {
+                let (_, output) = p.run_with_output(&[], true, o.mem_limit, time_limit);
+                self.depot.save_output(o.status, o.id, &o.origin, &output);
             }
-        };
-        ret
+        }
     }
 
     pub fn update_log(&mut self) {
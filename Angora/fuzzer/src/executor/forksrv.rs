@@ -5,14 +5,15 @@ use libc;
 use std::{
     collections::HashMap,
     fs,
-    io::prelude::*,
+    io::{self, prelude::*},
     os::unix::{
         io::RawFd,
         net::{UnixListener, UnixStream},
     },
     path::Path,
     process::{Command, Stdio},
-    time::Duration,
+    thread,
+    time::{Duration, Instant},
 };
 
 // Just meaningless value for forking a new child
@@ -22,8 +23,9 @@ static FORKSRV_NEW_CHILD: [u8; 4] = [8, 8, 8, 8];
 pub struct Forksrv {
     path: String,
     pub socket: UnixStream,
-    uses_asan: bool,
+    sanitizers: Vec<CrashKind>,
     is_stdin: bool,
+    time_limit: u64,
 }
 
 impl Forksrv {
@@ -33,9 +35,11 @@ impl Forksrv {
         envs: &HashMap<String, String>,
         fd: RawFd,
         is_stdin: bool,
-        uses_asan: bool,
+        sanitizers: &[CrashKind],
         time_limit: u64,
         mem_limit: u64,
+        init_timeout: u64,
+        output: Option<&fs::File>,
     ) -> Forksrv {
         debug!("socket_path: {:?}", socket_path);
         let listener = match UnixListener::bind(socket_path) {
@@ -49,32 +53,58 @@ impl Forksrv {
         let mut envs_fk = envs.clone();
         envs_fk.insert(ENABLE_FORKSRV.to_string(), String::from("TRUE"));
         envs_fk.insert(FORKSRV_SOCKET_PATH_VAR.to_string(), socket_path.to_owned());
-        match Command::new(&target.0)
+        let mut child = match Command::new(&target.0)
             .args(&target.1)
             .stdin(Stdio::null())
             .envs(&envs_fk)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
+            .stdout(output_stdio(output))
+            .stderr(output_stdio(output))
             .mem_limit(mem_limit.clone())
             .setsid()
             .pipe_stdin(fd, is_stdin)
             .spawn()
         {
-            Ok(_) => (),
+            Ok(c) => c,
             Err(e) => {
                 error!("FATAL: Failed to spawn child. Reason: {}", e);
                 panic!();
             }
         };
 
-        // FIXME: block here if client doesn't exist.
-        let (socket, _) = match listener.accept() {
-            Ok(a) => a,
-            Err(e) => {
-                error!("FATAL: failed to accept from socket: {:?}", e);
+        // The program may connect later, e.g. after __ANGORA_INIT().
+        listener
+            .set_nonblocking(true)
+            .expect("Couldn't set non-blocking");
+        let t_start = Instant::now();
+        let socket = loop {
+            match listener.accept() {
+                Ok((sock, _)) => break sock,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {},
+                Err(e) => {
+                    error!("FATAL: failed to accept from socket: {:?}", e);
+                    panic!();
+                }
+            }
+            if let Ok(Some(status)) = child.try_wait() {
+                error!(
+                    "FATAL: The program exited before the fork server is up: {}",
+                    status
+                );
+                panic!();
+            }
+            if t_start.elapsed() > Duration::from_secs(init_timeout) {
+                error!(
+                    "FATAL: The fork server is not up in {} seconds, increase forksrv_init_timeout if it takes longer to initialize",
+                    init_timeout
+                );
+                let _ = child.kill();
                 panic!();
             }
+            thread::sleep(Duration::from_millis(10));
         };
+        socket
+            .set_nonblocking(false)
+            .expect("Couldn't set blocking");
 
         socket
             .set_read_timeout(Some(Duration::from_secs(time_limit)))
@@ -88,12 +118,22 @@ impl Forksrv {
         Forksrv {
             path: socket_path.to_owned(),
             socket,
-            uses_asan,
+            sanitizers: sanitizers.to_vec(),
             is_stdin,
+            time_limit,
         }
     }
 
     pub fn run(&mut self) -> StatusType {
+        self.run_child(None)
+    }
+
+    // Send the input to the child over the network, and stop it then.
+    pub fn run_net(&mut self, net: &NetOpt, buf: &[u8]) -> StatusType {
+        self.run_child(Some((net, buf)))
+    }
+
+    fn run_child(&mut self, net: Option<(&NetOpt, &[u8])>) -> StatusType {
         if self.socket.write(&FORKSRV_NEW_CHILD).is_err() {
             warn!("Fail to write socket!!");
             return StatusType::Error;
@@ -124,6 +164,20 @@ impl Forksrv {
             }
         }
 
+        let stopped = match net {
+            Some((net, input)) => {
+                let timeout = Duration::from_secs(self.time_limit);
+                // The fork server reaps the child once it exits.
+                let is_alive = || unsafe { libc::kill(child_pid, 0) } == 0;
+                if let Err(e) = net.deliver(input, timeout, is_alive) {
+                    debug!("Could not deliver the input: {:?}", e);
+                }
+                net.stop(child_pid, is_alive);
+                true
+            },
+            None => false,
+        };
+
         buf = vec![0; 4];
 
         let read_result = self.socket.read(&mut buf);
@@ -138,10 +192,19 @@ impl Forksrv {
                     }
                 };
                 let exit_code = unsafe { libc::WEXITSTATUS(status) };
-                let signaled = unsafe { libc::WIFSIGNALED(status) };
-                if signaled || (self.uses_asan && exit_code == MSAN_ERROR_CODE) {
+                let signaled = unsafe { libc::WIFSIGNALED(status) }
+                    && !(stopped && net::is_stopped_by_signal(libc::WTERMSIG(status)));
+                if signaled {
                     debug!("Crash code: {}", status);
-                    StatusType::Crash
+                    StatusType::Crash(CrashKind::Signal)
+                } else if libc::WIFEXITED(status) {
+                    match CrashKind::from_exit_code(&self.sanitizers, exit_code) {
+                        Some(kind) => {
+                            debug!("Crash code: {}", status);
+                            StatusType::Crash(kind)
+                        },
+                        None => StatusType::Normal,
+                    }
                 } else {
                     StatusType::Normal
                 }
@@ -161,6 +224,14 @@ impl Forksrv {
     }
 }
 
+// The children of the fork server inherit them.
+fn output_stdio(output: Option<&fs::File>) -> Stdio {
+    match output.and_then(|f| f.try_clone().ok()) {
+        Some(f) => Stdio::from(f),
+        None => Stdio::null(),
+    }
+}
+
 impl Drop for Forksrv {
     fn drop(&mut self) {
         debug!("Exit Forksrv");
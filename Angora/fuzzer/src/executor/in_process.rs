@@ -0,0 +1,194 @@
+// Run a closure in the fuzzer as the program, with the feedback simulated
+// the way the runtime gives it, for deterministic tests of the searches.
+use super::*;
+use crate::branches::BranchBuf;
+use angora_common::{cond_stmt_base::CondStmtBase, config::BRANCHES_SIZE};
+
+pub struct Feedback<'a> {
+    trace: &'a mut BranchBuf,
+    cond: &'a mut CondStmtBase,
+    rt_order: u32,
+}
+
+impl<'a> Feedback<'a> {
+    // An edge between two basic blocks.
+    pub fn hit(&mut self, edge: usize) {
+        let idx = edge % BRANCHES_SIZE;
+        self.trace[idx] = self.trace[idx].wrapping_add(1);
+    }
+
+    // As `__angora_trace_cmp` in runtime_fast.
+    pub fn trace_cmp(
+        &mut self,
+        cmpid: u32,
+        context: u32,
+        condition: u32,
+        arg1: u64,
+        arg2: u64,
+    ) -> u32 {
+        if self.cond.cmpid == cmpid && self.cond.context == context {
+            self.rt_order += 1;
+            if self.cond.order & 0xFFFF == self.rt_order {
+                self.cond.arg1 = arg1;
+                self.cond.arg2 = arg2;
+                self.cond.lb1 = condition;
+                self.rt_order = 0x8000;
+            }
+        }
+        condition
+    }
+}
+
+pub struct InProcessBackend<F>
+where
+    F: FnMut(&[u8], &mut Feedback) -> StatusType,
+{
+    target: F,
+    input: Vec<u8>,
+}
+
+impl<F> InProcessBackend<F>
+where
+    F: FnMut(&[u8], &mut Feedback) -> StatusType,
+{
+    pub fn new(target: F) -> Self {
+        Self {
+            target,
+            input: vec![],
+        }
+    }
+}
+
+impl<F> ExecBackend for InProcessBackend<F>
+where
+    F: FnMut(&[u8], &mut Feedback) -> StatusType,
+{
+    fn write_input(&mut self, buf: &[u8]) {
+        self.input.clear();
+        self.input.extend_from_slice(buf);
+    }
+
+    fn run(&mut self, trace: &mut BranchBuf, cond: &mut CondStmtBase) -> StatusType {
+        let mut fb = Feedback {
+            trace,
+            cond,
+            rt_order: 0,
+        };
+        (self.target)(&self.input, &mut fb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        branches::GlobalBranches,
+        command::CommandOpt,
+        cond_stmt::CondStmt,
+        depot::{Depot, QueuePolicy},
+        fuzz_config::Config,
+        search::{FnFuzz, GdSearch, SearchHandler},
+        stats::ChartStats,
+    };
+    use angora_common::{defs, tag::TagSeg};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::{
+        fs,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, RwLock,
+        },
+    };
+
+    const CMPID: u32 = 7;
+    const MAGIC: u32 = 0x1234_5678;
+
+    // if (*(uint32_t *)buf == MAGIC) abort();
+    fn target(buf: &[u8], fb: &mut Feedback) -> StatusType {
+        let mut v = [0u8; 4];
+        let n = buf.len().min(4);
+        v[..n].copy_from_slice(&buf[..n]);
+        let v = u32::from_le_bytes(v);
+        fb.hit(1);
+        if fb.trace_cmp(CMPID, 0, (v == MAGIC) as u32, v as u64, MAGIC as u64) == 1 {
+            fb.hit(2);
+            StatusType::Crash(CrashKind::Signal)
+        } else {
+            StatusType::Normal
+        }
+    }
+
+    fn magic_cond(op: u32) -> CondStmt {
+        let mut cond = CondStmt::new();
+        cond.base.cmpid = CMPID;
+        cond.base.order = 1;
+        cond.base.op = op;
+        cond.base.size = 4;
+        cond.base.condition = defs::COND_FALSE_ST;
+        cond.offsets = vec![TagSeg {
+            sign: false,
+            begin: 0,
+            end: 4,
+        }];
+        cond
+    }
+
+    // Run the search on an executor of `target`, and count the crashes.
+    fn search_magic(name: &'static str, mut cond: CondStmt) -> (CondStmt, usize) {
+        let t = std::thread::Builder::new().stack_size(32 << 20).spawn(move || {
+            let out_dir = std::env::temp_dir().join(format!("angora_test_{}", name));
+            let _ = fs::remove_dir_all(&out_dir);
+            fs::create_dir_all(&out_dir).unwrap();
+            let depot = Arc::new(Depot::new(
+                out_dir.join("seeds"),
+                &out_dir,
+                QueuePolicy::Legacy,
+                &Config::default(),
+            ));
+            let mut executor = Executor::new_in_process(
+                CommandOpt::for_test(&out_dir),
+                Arc::new(GlobalBranches::new()),
+                depot.clone(),
+                Arc::new(RwLock::new(ChartStats::new())),
+                Arc::new(RwLock::new(Default::default())),
+                target,
+            );
+            let running = Arc::new(AtomicBool::new(true));
+            let buf = vec![0u8; 8];
+            executor.run_sync(&buf);
+            let handler = SearchHandler::new(running.clone(), &mut executor, &mut cond, buf);
+            if name == "gd" {
+                GdSearch::new(handler, false).run(&mut StdRng::seed_from_u64(0));
+            } else {
+                FnFuzz::new(handler).run();
+            }
+            let num_crashes = depot.num_crashes.load(Ordering::Relaxed);
+            // The depot is dumped to the directory when it is dropped.
+            drop(executor);
+            drop(depot);
+            let _ = fs::remove_dir_all(&out_dir);
+            (cond, num_crashes)
+        });
+        t.unwrap().join().unwrap()
+    }
+
+    #[test]
+    fn gd_search_in_process() {
+        let mut cond = magic_cond(defs::COND_ICMP_EQ_OP);
+        cond.variables = vec![0; 4];
+        let (cond, num_crashes) = search_magic("gd", cond);
+        assert!(cond.is_done());
+        assert_eq!(num_crashes, 1);
+    }
+
+    #[test]
+    fn fn_fuzz_in_process() {
+        let mut cond = magic_cond(defs::COND_FN_OP);
+        // The magic bytes, then what the program read at the offsets.
+        cond.variables = MAGIC.to_le_bytes().to_vec();
+        cond.variables.extend_from_slice(&[0; 4]);
+        let (cond, num_crashes) = search_magic("fn", cond);
+        assert!(cond.is_done());
+        assert_eq!(num_crashes, 1);
+    }
+}
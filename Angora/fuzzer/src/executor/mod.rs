@@ -1,8 +1,24 @@
+mod backend;
 mod executor;
 mod forksrv;
+mod in_process;
 mod limit;
+mod net;
 mod pipe_fd;
+mod process;
+mod sanitizer;
 mod status_type;
+mod trace_runner;
 
 use self::pipe_fd::PipeFd;
-pub use self::{executor::Executor, forksrv::Forksrv, status_type::StatusType};
+pub use self::{
+    backend::ExecBackend,
+    executor::Executor,
+    forksrv::Forksrv,
+    in_process::{Feedback, InProcessBackend},
+    net::{has_port_arg, subst_port_arg, NetOpt, NetProto, PORT_ARG},
+    process::ProcessBackend,
+    sanitizer::{CrashKind, CRASH_KIND_NUM},
+    status_type::StatusType,
+    trace_runner::TraceRunner,
+};
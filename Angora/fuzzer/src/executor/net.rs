@@ -0,0 +1,208 @@
+// Deliver the input to a program that reads it from a socket, e.g. a server.
+// The program is started as usual, then the input is sent to the port on
+// localhost, and the program is stopped with SIGTERM after handling it.
+use libc;
+use std::{
+    fs,
+    io::{self, prelude::*},
+    net::{Shutdown, TcpStream, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+// Replaced by the port of each executor in the arguments, e.g. `-p @@port`.
+pub static PORT_ARG: &str = "@@port";
+
+pub fn has_port_arg(args: &[String]) -> bool {
+    args.iter().any(|a| a.contains(PORT_ARG))
+}
+
+pub fn subst_port_arg(arg: &mut String, port: u16) {
+    if arg.contains(PORT_ARG) {
+        *arg = arg.replace(PORT_ARG, &port.to_string());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NetProto {
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetOpt {
+    pub proto: NetProto,
+    pub port: u16,
+    // Wait for the response, or the connection to be closed.
+    pub wait_response: bool,
+    // ms
+    pub wait_time: u64,
+}
+
+impl NetOpt {
+    // e.g. tcp:8080, udp:5353
+    pub fn parse(s: &str, wait_response: bool, wait_time: u64) -> Result<Self, String> {
+        let mut it = s.splitn(2, ':');
+        let proto = match it.next() {
+            Some("tcp") => NetProto::Tcp,
+            Some("udp") => NetProto::Udp,
+            _ => return Err(format!("{} is not tcp:PORT or udp:PORT", s)),
+        };
+        let port = it
+            .next()
+            .and_then(|p| p.parse::<u16>().ok())
+            .ok_or_else(|| format!("Invalid port in {}", s))?;
+        Ok(Self {
+            proto,
+            port,
+            wait_response,
+            wait_time,
+        })
+    }
+
+    // Send the input once the program listens on the port.
+    // `is_alive` tells if the program is still running, e.g. not crashed before listening.
+    pub fn deliver<F>(&self, buf: &[u8], timeout: Duration, mut is_alive: F) -> io::Result<()>
+    where
+        F: FnMut() -> bool,
+    {
+        let t_start = Instant::now();
+        let addr = ("127.0.0.1", self.port);
+        let remaining = || {
+            timeout
+                .checked_sub(t_start.elapsed())
+                .filter(|d| *d > Duration::from_millis(0))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "The program is not listening"))
+        };
+        loop {
+            let ready = match self.proto {
+                NetProto::Tcp => match TcpStream::connect(addr) {
+                    Ok(stream) => return self.send_tcp(stream, buf, remaining()?),
+                    Err(ref e) if e.kind() == io::ErrorKind::ConnectionRefused => false,
+                    Err(e) => return Err(e),
+                },
+                // Not bind it to check, which would take it from the program.
+                NetProto::Udp => is_udp_bound(self.port),
+            };
+            if ready {
+                return self.send_udp(buf, remaining()?);
+            }
+            if !is_alive() {
+                return Err(io::Error::new(io::ErrorKind::NotConnected, "The program exited"));
+            }
+            remaining()?;
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn send_tcp(&self, mut stream: TcpStream, buf: &[u8], timeout: Duration) -> io::Result<()> {
+        stream.set_write_timeout(Some(timeout))?;
+        stream.write_all(buf)?;
+        // The end of the input, for the programs reading until EOF.
+        stream.shutdown(Shutdown::Write)?;
+        if self.wait_response {
+            stream.set_read_timeout(Some(timeout))?;
+            let mut res = [0u8; 1024];
+            if stream.read(&mut res)? == 0 {
+                debug!("The program closed the connection without a response.");
+            }
+        }
+        Ok(())
+    }
+
+    fn send_udp(&self, buf: &[u8], timeout: Duration) -> io::Result<()> {
+        let socket = UdpSocket::bind(("127.0.0.1", 0))?;
+        socket.send_to(buf, ("127.0.0.1", self.port))?;
+        if self.wait_response {
+            socket.set_read_timeout(Some(timeout))?;
+            let mut res = [0u8; 65536];
+            socket.recv_from(&mut res)?;
+        }
+        Ok(())
+    }
+
+    // Give the program some time to handle the input, then stop it.
+    pub fn stop<F>(&self, pid: i32, mut is_alive: F)
+    where
+        F: FnMut() -> bool,
+    {
+        if self.wait_time > 0 {
+            thread::sleep(Duration::from_millis(self.wait_time));
+        }
+        if is_alive() {
+            unsafe {
+                libc::kill(pid, libc::SIGTERM);
+            }
+        }
+    }
+}
+
+// A socket is bound to the UDP port, in the tables of the kernel, e.g.
+//   sl  local_address rem_address   st ...
+//    0: 0100007F:14E9 00000000:0000 07 ...
+fn is_udp_bound(port: u16) -> bool {
+    ["/proc/net/udp", "/proc/net/udp6"].iter().any(|f| {
+        fs::read_to_string(f).is_ok_and(|table| has_local_port(&table, port))
+    })
+}
+
+fn has_local_port(table: &str, port: u16) -> bool {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|l| l.split_whitespace().nth(1))
+        .filter_map(|addr| addr.rsplit(':').next())
+        .any(|p| u16::from_str_radix(p, 16) == Ok(port))
+}
+
+// Killed by `stop` rather than crashed.
+pub fn is_stopped_by_signal(signal: i32) -> bool {
+    signal == libc::SIGTERM
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_net_deliver() {
+        assert!(NetOpt::parse("http:80", false, 0).is_err());
+        assert!(NetOpt::parse("tcp:abc", false, 0).is_err());
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let net = NetOpt::parse(&format!("tcp:{}", port), true, 0).unwrap();
+        assert_eq!(net.proto, NetProto::Tcp);
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut req = vec![];
+            stream.read_to_end(&mut req).unwrap();
+            stream.write_all(b"ok").unwrap();
+            req
+        });
+        net.deliver(b"GET /", Duration::from_secs(5), || true).unwrap();
+        assert_eq!(server.join().unwrap(), b"GET /");
+
+        let socket = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let net = NetOpt::parse(&format!("udp:{}", port), false, 0).unwrap();
+        net.deliver(b"query", Duration::from_secs(5), || true).unwrap();
+        let mut buf = [0u8; 16];
+        let (n, _) = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"query");
+
+        let table = "  sl  local_address rem_address   st\n   0: 0100007F:14E9 00000000:0000 07\n";
+        assert!(has_local_port(table, 5353));
+        assert!(!has_local_port(table, 53));
+    }
+
+    #[test]
+    fn test_port_arg() {
+        let mut args = vec!["-v".to_string(), "--port=@@port".to_string()];
+        assert!(has_port_arg(&args));
+        args.iter_mut().for_each(|a| subst_port_arg(a, 8082));
+        assert_eq!(args, vec!["-v", "--port=8082"]);
+        assert!(!has_port_arg(&args));
+    }
+}
@@ -24,7 +24,7 @@ impl PipeFd {
         self.file.as_raw_fd()
     }
 
-    pub fn write_buf(&mut self, buf: &Vec<u8>) {
+    pub fn write_buf(&mut self, buf: &[u8]) {
         self.file.seek(SeekFrom::Start(0)).unwrap();
         self.file.write(buf).unwrap();
         self.file.set_len(buf.len() as u64).unwrap();
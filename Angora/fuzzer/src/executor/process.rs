@@ -0,0 +1,420 @@
+// Run the compiled programs in processes, the fast one with the fork server.
+use super::{limit::SetLimit, *};
+use crate::{branches::BranchBuf, command, multi_input};
+use angora_common::{
+    cond_stmt_base::CondStmtBase, config, defs, shm, shm_input::ShmInput,
+};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fs,
+    io::{prelude::*, SeekFrom},
+    os::unix::{ffi::OsStringExt, process::ExitStatusExt},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time,
+};
+use wait_timeout::ChildExt;
+
+static REPORT_FILE: &str = "report";
+static OUTPUT_FILE: &str = "target_output";
+static ARG_INPUT_FILE: &str = "arg_input";
+
+pub struct ProcessBackend {
+    cmd: command::CommandOpt,
+    envs: HashMap<String, String>,
+    forksrv: Option<Forksrv>,
+    fd: PipeFd,
+    // All the output of the program, for debugging.
+    output: Option<fs::File>,
+    output_path: PathBuf,
+    shm_input: Option<shm::SHM<ShmInput>>,
+    // The last input, sent over the network or mapped onto the arguments.
+    input: Vec<u8>,
+    // The arguments from the input for the children of the fork server.
+    arg_file: PathBuf,
+}
+
+impl ProcessBackend {
+    // The programs write the branch trace and the condition output
+    // to the shared memory of `branches_id` and `cond_id`.
+    pub fn new(cmd: &command::CommandOpt, branches_id: i32, cond_id: i32) -> Self {
+        // ** Envs **
+        let mut envs = HashMap::new();
+        for (var, options) in CrashKind::options_envs(false).iter() {
+            envs.insert(var.to_string(), options.to_string());
+        }
+        envs.insert(defs::BRANCHES_SHM_ENV_VAR.to_string(), branches_id.to_string());
+        envs.insert(defs::COND_STMT_ENV_VAR.to_string(), cond_id.to_string());
+        envs.insert(
+            defs::LD_LIBRARY_PATH_VAR.to_string(),
+            cmd.ld_library.clone(),
+        );
+        envs.insert(
+            defs::TRACK_OUTPUT_VAR.to_string(),
+            cmd.track_path.clone(),
+        );
+        let shm_input = if cmd.shm_input {
+            let s = shm::SHM::<ShmInput>::new();
+            envs.insert(defs::SHM_INPUT_ENV_VAR.to_string(), s.get_id().to_string());
+            Some(s)
+        } else {
+            None
+        };
+        if cmd.is_persistent {
+            envs.insert(defs::PERSIST_ENV_VAR.to_string(), String::from("TRUE"));
+        }
+        if cmd.is_deferred {
+            envs.insert(defs::DEFER_ENV_VAR.to_string(), String::from("TRUE"));
+        }
+        if let Some(ref net) = cmd.net {
+            envs.insert(defs::NET_PORT_VAR.to_string(), net.port.to_string());
+        }
+        let arg_file = cmd.tmp_dir.join(format!("{}_{}", ARG_INPUT_FILE, cmd.id));
+        if cmd.arg_template.is_some() {
+            envs.insert(
+                defs::ARG_INPUT_FILE_VAR.to_string(),
+                arg_file.to_string_lossy().into_owned(),
+            );
+        }
+
+        let fd = pipe_fd::PipeFd::new(&cmd.out_file);
+        let output_path = cmd
+            .tmp_dir
+            .parent()
+            .unwrap_or(&cmd.tmp_dir)
+            .join(format!("{}_{}", OUTPUT_FILE, cmd.id));
+        let output = if env::var(defs::KEEP_OUTPUT_VAR).is_ok() {
+            match fs::OpenOptions::new().create(true).append(true).open(&output_path) {
+                Ok(f) => Some(f),
+                Err(e) => {
+                    warn!("Could not open {:?} for the output: {:?}", output_path, e);
+                    None
+                },
+            }
+        } else {
+            None
+        };
+
+        let mut backend = Self {
+            cmd: cmd.clone(),
+            envs,
+            forksrv: None,
+            fd,
+            output,
+            output_path,
+            shm_input,
+            input: vec![],
+            arg_file,
+        };
+        backend.rebind_forksrv();
+        backend
+    }
+
+    pub fn rebind_forksrv(&mut self) {
+        {
+            // delete the old forksrv
+            self.forksrv = None;
+        }
+        // The children replace the arguments, so their number should be the same.
+        let mut main = self.cmd.main.clone();
+        if let Some(ref t) = self.cmd.arg_template {
+            let args = t.render(&[], 1 + main.1.len()).input.args;
+            main.1
+                .extend(args.iter().map(|a| String::from_utf8_lossy(a).into_owned()));
+        }
+        let fs = forksrv::Forksrv::new(
+            &self.cmd.forksrv_socket_path,
+            &main,
+            &self.envs,
+            self.fd.as_raw_fd(),
+            self.cmd.is_stdin,
+            &self.cmd.sanitizers,
+            self.cmd.time_limit,
+            self.cmd.mem_limit,
+            self.cmd.config.forksrv_init_timeout,
+            self.output.as_ref(),
+        );
+        self.forksrv = Some(fs);
+    }
+
+    // With --shm_input, the fast program gets the input from the shared memory,
+    // and the file is only written if it is too large for it, or with --shm_input_file.
+    // The track program always reads the file.
+    pub fn write(&mut self, buf: &[u8], use_shm: bool) {
+        if self.cmd.net.is_some() || self.cmd.arg_template.is_some() {
+            self.input.clear();
+            self.input.extend_from_slice(buf);
+        }
+        if let Some(ref t) = self.cmd.arg_template {
+            let r = t.render(buf, 1 + self.cmd.main.1.len());
+            if let Err(e) = r.input.write(&self.arg_file) {
+                warn!("Could not write the arguments: {:?}", e);
+            }
+        }
+        if self.cmd.num_parts > 0 {
+            self.write_parts(buf);
+        }
+        let in_shm = match self.shm_input {
+            Some(ref mut s) if use_shm => s.set(buf),
+            _ => false,
+        };
+        if !in_shm || self.cmd.shm_input_file {
+            self.fd.write_buf(buf);
+            if self.cmd.is_stdin {
+                self.fd.rewind();
+            }
+        }
+    }
+
+    // Each part goes to its own file, and the track program learns
+    // where they are in the test case.
+    fn write_parts(&mut self, buf: &[u8]) {
+        let parts = multi_input::split(buf, self.cmd.num_parts);
+        for (i, r) in parts.iter().enumerate() {
+            let file = multi_input::part_file(&self.cmd.out_file, i + 1);
+            if let Err(e) = fs::write(&file, &buf[r.clone()]) {
+                warn!("Could not write the part {}: {:?}", file, e);
+            }
+        }
+        let offsets: Vec<String> = parts.iter().map(|r| r.start.to_string()).collect();
+        self.envs
+            .insert(defs::INPUT_PARTS_VAR.to_string(), offsets.join(","));
+    }
+
+    pub fn run_target(
+        &mut self,
+        target: &(String, Vec<String>),
+        mem_limit: u64,
+        time_limit: u64,
+    ) -> StatusType {
+        if self.cmd.is_stdin {
+            self.fd.rewind();
+        }
+        let (args, envs) = self.template_args(target);
+        let mut cmd = Command::new(&target.0);
+        let mut child = cmd
+            .args(&target.1)
+            .args(args)
+            .stdin(Stdio::null())
+            .env_clear()
+            .envs(&self.envs)
+            .envs(envs)
+            .stdout(self.output_stdio())
+            .stderr(self.output_stdio())
+            .mem_limit(mem_limit.clone())
+            .setsid()
+            .pipe_stdin(self.fd.as_raw_fd(), self.cmd.is_stdin)
+            .spawn()
+            .expect("Could not run target");
+        let stopped = self.deliver(&mut child, time_limit);
+
+        let timeout = time::Duration::from_secs(time_limit);
+        let ret = match child.wait_timeout(timeout).unwrap() {
+            Some(status) => {
+                if stopped && status.signal().is_some_and(net::is_stopped_by_signal) {
+                    StatusType::Normal
+                } else if let Some(status_code) = status.code() {
+                    if let Some(kind) = CrashKind::from_exit_code(&self.cmd.sanitizers, status_code)
+                    {
+                        StatusType::Crash(kind)
+                    } else if self.cmd.mode.is_pin_mode() && status_code > 128 {
+                        StatusType::Crash(CrashKind::Signal)
+                    } else {
+                        StatusType::Normal
+                    }
+                } else {
+                    StatusType::Crash(CrashKind::Signal)
+                }
+            }
+            None => {
+                // Timeout
+                // child hasn't exited yet
+                child.kill().expect("Could not send kill signal to child.");
+                child.wait().expect("Error during waiting for child.");
+                StatusType::Timeout
+            }
+        };
+        ret
+    }
+
+    // The arguments and envs from the input, after the ones of `target`,
+    // and where they are in the input for the track program.
+    fn template_args(
+        &self,
+        target: &(String, Vec<String>),
+    ) -> (Vec<OsString>, Vec<(OsString, OsString)>) {
+        let t = match self.cmd.arg_template {
+            Some(ref t) => t,
+            None => return (vec![], vec![]),
+        };
+        let r = t.render(&self.input, 1 + target.1.len());
+        let args = r.input.args.into_iter().map(OsString::from_vec).collect();
+        let mut envs: Vec<(OsString, OsString)> = r
+            .input
+            .envs
+            .into_iter()
+            .map(|(k, v)| (OsString::from(k), OsString::from_vec(v)))
+            .collect();
+        envs.push((defs::TAINT_ARGS_VAR.into(), r.taint_args.into()));
+        envs.push((defs::TAINT_ENVS_VAR.into(), r.taint_envs.into()));
+        (args, envs)
+    }
+
+    // Send the input to the spawned program if it reads from the network,
+    // and stop the program then.
+    fn deliver(&self, child: &mut Child, time_limit: u64) -> bool {
+        let net = match self.cmd.net {
+            Some(ref net) => net,
+            None => return false,
+        };
+        let timeout = time::Duration::from_secs(time_limit);
+        let pid = child.id() as i32;
+        if let Err(e) = net.deliver(&self.input, timeout, || is_alive(child)) {
+            debug!("Could not deliver the input: {:?}", e);
+        }
+        net.stop(pid, || is_alive(child));
+        true
+    }
+
+    // Keep the last KEEP_OUTPUT_SIZE bytes or more of the output, in two files.
+    // The children append to the same file, so it is truncated in place.
+    fn rotate_output(&mut self) {
+        let f = match self.output {
+            Some(ref f) => f,
+            None => return,
+        };
+        if f.metadata().map_or(true, |m| m.len() < config::KEEP_OUTPUT_SIZE) {
+            return;
+        }
+        let old = self.output_path.with_extension("1");
+        if let Err(e) = fs::copy(&self.output_path, &old).and_then(|_| f.set_len(0)) {
+            warn!("Could not rotate {:?}: {:?}", self.output_path, e);
+            self.output = None;
+        }
+    }
+
+    fn output_stdio(&self) -> Stdio {
+        match self.output.as_ref().and_then(|f| f.try_clone().ok()) {
+            Some(f) => Stdio::from(f),
+            None => Stdio::null(),
+        }
+    }
+
+    // Run the fast program directly on the written input, and get the last
+    // OUTPUT_LOG_SIZE bytes it prints to stderr (and stdout).
+    // `envs` are added to the ones of the fuzzer.
+    pub fn run_with_output(
+        &mut self,
+        envs: &[(&str, &str)],
+        with_stdout: bool,
+        mem_limit: u64,
+        time_limit: u64,
+    ) -> (StatusType, Vec<u8>) {
+        let output_path = self.cmd.tmp_dir.join(format!("{}_{}", REPORT_FILE, self.cmd.id));
+        let output_f = match fs::File::create(&output_path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Could not create output file: {:?}", e);
+                return (StatusType::Error, vec![]);
+            },
+        };
+        let stdout = match output_f.try_clone() {
+            Ok(f) if with_stdout => Stdio::from(f),
+            _ => Stdio::null(),
+        };
+
+        if self.cmd.is_stdin {
+            self.fd.rewind();
+        }
+        let (args, t_envs) = self.template_args(&self.cmd.main);
+        let mut child = Command::new(&self.cmd.main.0)
+            .args(&self.cmd.main.1)
+            .args(args)
+            .stdin(Stdio::null())
+            // Keep PATH for finding llvm-symbolizer.
+            .envs(&self.envs)
+            .envs(t_envs)
+            .envs(envs.iter().cloned())
+            .stdout(stdout)
+            .stderr(Stdio::from(output_f))
+            .mem_limit(mem_limit)
+            .setsid()
+            .pipe_stdin(self.fd.as_raw_fd(), self.cmd.is_stdin)
+            .spawn()
+            .expect("Could not run target");
+        let stopped = self.deliver(&mut child, time_limit);
+
+        let timeout = time::Duration::from_secs(time_limit);
+        let status = match child.wait_timeout(timeout).unwrap() {
+            Some(status) => {
+                if stopped && status.signal().is_some_and(net::is_stopped_by_signal) {
+                    StatusType::Normal
+                } else if status.signal().is_some() {
+                    StatusType::Crash(CrashKind::Signal)
+                } else {
+                    status
+                        .code()
+                        .and_then(|c| CrashKind::from_exit_code(&self.cmd.sanitizers, c))
+                        .map_or(StatusType::Normal, StatusType::Crash)
+                }
+            },
+            None => {
+                child.kill().expect("Could not send kill signal to child.");
+                child.wait().expect("Error during waiting for child.");
+                StatusType::Timeout
+            },
+        };
+
+        let output = read_tail(&output_path, config::OUTPUT_LOG_SIZE);
+        let _ = fs::remove_file(&output_path);
+        (status, output)
+    }
+}
+
+impl ExecBackend for ProcessBackend {
+    fn write_input(&mut self, buf: &[u8]) {
+        self.write(buf, true);
+    }
+
+    // The program writes the feedback to the shared memory.
+    fn run(&mut self, _trace: &mut BranchBuf, _cond: &mut CondStmtBase) -> StatusType {
+        if self.cmd.is_stdin {
+            self.fd.rewind();
+        }
+        let main = self.cmd.main.clone();
+        let status = match (self.forksrv.as_mut(), self.cmd.net.as_ref()) {
+            (Some(fs), Some(net)) => fs.run_net(net, &self.input),
+            (Some(fs), None) => fs.run(),
+            (None, _) => self.run_target(&main, self.cmd.mem_limit, self.cmd.time_limit),
+        };
+        self.rotate_output();
+        status
+    }
+
+    fn restart(&mut self) {
+        self.rebind_forksrv();
+    }
+
+    fn as_process(&mut self) -> Option<&mut ProcessBackend> {
+        Some(self)
+    }
+}
+
+fn is_alive(child: &mut Child) -> bool {
+    matches!(child.try_wait(), Ok(None))
+}
+
+// The end of the output is more useful, e.g. the report of sanitizers.
+fn read_tail(path: &Path, size: usize) -> Vec<u8> {
+    let mut buf = vec![];
+    if let Ok(mut f) = fs::File::open(path) {
+        let len = f.metadata().map(|m| m.len()).unwrap_or(0);
+        let start = len.saturating_sub(size as u64);
+        if f.seek(SeekFrom::Start(start)).is_ok() {
+            let _ = f.take(size as u64).read_to_end(&mut buf);
+        }
+    }
+    buf
+}
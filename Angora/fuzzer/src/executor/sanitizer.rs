@@ -0,0 +1,130 @@
+// What finds a crash: a signal, or one of the sanitizers built in the program,
+// told apart by their exit codes (see common/src/defs.rs).
+use angora_common::defs;
+
+pub const CRASH_KIND_NUM: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CrashKind {
+    Signal,
+    Asan,
+    Msan,
+    Ubsan,
+    Tsan,
+    Lsan,
+}
+
+impl CrashKind {
+    pub const ALL: [CrashKind; CRASH_KIND_NUM] = [
+        CrashKind::Signal,
+        CrashKind::Asan,
+        CrashKind::Msan,
+        CrashKind::Ubsan,
+        CrashKind::Tsan,
+        CrashKind::Lsan,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CrashKind::Signal => "signal",
+            CrashKind::Asan => "asan",
+            CrashKind::Msan => "msan",
+            CrashKind::Ubsan => "ubsan",
+            CrashKind::Tsan => "tsan",
+            CrashKind::Lsan => "lsan",
+        }
+    }
+
+    // The sanitizers given by name with --sanitizers.
+    pub fn from_name(name: &str) -> Option<CrashKind> {
+        CrashKind::ALL
+            .iter()
+            .skip(1)
+            .find(|s| s.name() == name)
+            .cloned()
+    }
+
+    // The symbols of its runtime in the binary.
+    pub fn signatures(self) -> &'static [&'static str] {
+        match self {
+            CrashKind::Signal => &[],
+            CrashKind::Asan => &["__asan_init", "libasan.so"],
+            CrashKind::Msan => &["__msan_init"],
+            CrashKind::Ubsan => &["__ubsan_handle_", "libubsan.so"],
+            CrashKind::Tsan => &["__tsan_init", "libtsan.so"],
+            // ASAN has LSAN in it, so only the standalone one.
+            CrashKind::Lsan => &["__lsan_init", "liblsan.so"],
+        }
+    }
+
+    fn error_code(self) -> Option<i32> {
+        match self {
+            CrashKind::Signal => None,
+            CrashKind::Asan => Some(defs::ASAN_ERROR_CODE),
+            CrashKind::Msan => Some(defs::MSAN_ERROR_CODE),
+            CrashKind::Ubsan => Some(defs::UBSAN_ERROR_CODE),
+            CrashKind::Tsan => Some(defs::TSAN_ERROR_CODE),
+            CrashKind::Lsan => Some(defs::LSAN_ERROR_CODE),
+        }
+    }
+
+    // They reserve terabytes of shadow memory, which can not be limited.
+    pub fn needs_unlimited_memory(self) -> bool {
+        matches!(self, CrashKind::Asan | CrashKind::Msan | CrashKind::Tsan)
+    }
+
+    // The sanitizer that exits with `code`, among the ones in the program.
+    pub fn from_exit_code(sanitizers: &[CrashKind], code: i32) -> Option<CrashKind> {
+        sanitizers
+            .iter()
+            .find(|s| s.error_code() == Some(code))
+            .cloned()
+    }
+
+    // The options of all the sanitizers, symbolized ones for the reports of crash triage.
+    pub fn options_envs(symbolize: bool) -> [(&'static str, &'static str); 5] {
+        if symbolize {
+            [
+                (defs::ASAN_OPTIONS_VAR, defs::ASAN_OPTIONS_SYMBOLIZE_CONTENT),
+                (defs::MSAN_OPTIONS_VAR, defs::MSAN_OPTIONS_SYMBOLIZE_CONTENT),
+                (defs::UBSAN_OPTIONS_VAR, defs::UBSAN_OPTIONS_SYMBOLIZE_CONTENT),
+                (defs::TSAN_OPTIONS_VAR, defs::TSAN_OPTIONS_SYMBOLIZE_CONTENT),
+                (defs::LSAN_OPTIONS_VAR, defs::LSAN_OPTIONS_SYMBOLIZE_CONTENT),
+            ]
+        } else {
+            [
+                (defs::ASAN_OPTIONS_VAR, defs::ASAN_OPTIONS_CONTENT),
+                (defs::MSAN_OPTIONS_VAR, defs::MSAN_OPTIONS_CONTENT),
+                (defs::UBSAN_OPTIONS_VAR, defs::UBSAN_OPTIONS_CONTENT),
+                (defs::TSAN_OPTIONS_VAR, defs::TSAN_OPTIONS_CONTENT),
+                (defs::LSAN_OPTIONS_VAR, defs::LSAN_OPTIONS_CONTENT),
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_exit_code() {
+        let sanitizers = [CrashKind::Asan, CrashKind::Ubsan];
+        assert_eq!(
+            CrashKind::from_exit_code(&sanitizers, defs::UBSAN_ERROR_CODE),
+            Some(CrashKind::Ubsan)
+        );
+        // The program may exit with it by itself.
+        assert_eq!(CrashKind::from_exit_code(&sanitizers, defs::MSAN_ERROR_CODE), None);
+        assert_eq!(CrashKind::from_exit_code(&sanitizers, 0), None);
+        assert_eq!(CrashKind::from_name("tsan"), Some(CrashKind::Tsan));
+        assert_eq!(CrashKind::from_name("signal"), None);
+        for (i, k) in CrashKind::ALL.iter().enumerate() {
+            assert_eq!(k.index(), i);
+        }
+    }
+}
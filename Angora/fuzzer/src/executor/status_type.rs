@@ -1,8 +1,16 @@
+use super::CrashKind;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StatusType {
     Normal,
     Timeout,
-    Crash,
+    Crash(CrashKind),
     Skip,
     Error,
 }
+
+impl StatusType {
+    pub fn is_crash(&self) -> bool {
+        matches!(self, StatusType::Crash(_))
+    }
+}
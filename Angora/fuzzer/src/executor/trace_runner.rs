@@ -0,0 +1,162 @@
+// A small runner for the tools (angora-cmin, angora-tmin) that only need the
+// status and the trace of an input, without the depot and the search.
+use super::{limit::SetLimit, *};
+use crate::{
+    branches::{Branches, GlobalBranches},
+    check_dep, cond_stmt::CondStmt, tmpfs, track,
+};
+use angora_common::{config, defs};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Arc,
+    time,
+};
+use wait_timeout::ChildExt;
+
+static INPUT_FILE: &str = "cur_input";
+static FORKSRV_SOCKET_FILE: &str = "forksrv_socket";
+static TRACK_FILE: &str = "track";
+
+pub struct TraceRunner {
+    branches: Branches,
+    fd: PipeFd,
+    forksrv: Option<Forksrv>,
+    envs: HashMap<String, String>,
+    track: Option<(String, Vec<String>)>,
+    track_path: String,
+    tmp_dir: PathBuf,
+    is_stdin: bool,
+}
+
+impl TraceRunner {
+    // `pargs` is the fast program and its arguments, and `track_bin` is
+    // the track program built with the same source.
+    pub fn new(
+        pargs: &[String],
+        track_bin: Option<&str>,
+        tmp_dir: PathBuf,
+        mem_limit: u64,
+        time_limit: u64,
+    ) -> Self {
+        tmpfs::create_tmpfs_dir(&tmp_dir);
+        let input_file = tmp_dir.join(INPUT_FILE).to_str().unwrap().to_owned();
+        let socket_path = tmp_dir.join(FORKSRV_SOCKET_FILE).to_str().unwrap().to_owned();
+        let track_path = tmp_dir.join(TRACK_FILE).to_str().unwrap().to_owned();
+
+        let is_stdin = !pargs.iter().any(|a| a == "@@");
+        let main_bin = pargs[0].clone();
+        let main_args: Vec<String> = pargs[1..]
+            .iter()
+            .map(|a| if a == "@@" { input_file.clone() } else { a.clone() })
+            .collect();
+        let sanitizers = check_dep::check_sanitizers(&main_bin);
+        let mem_limit = if sanitizers.iter().any(|s| s.needs_unlimited_memory()) {
+            0
+        } else {
+            mem_limit
+        };
+        let track = track_bin.map(|t| (t.to_string(), main_args.clone()));
+
+        let branches = Branches::new(Arc::new(GlobalBranches::new()));
+        let mut envs = HashMap::new();
+        for (var, options) in CrashKind::options_envs(false).iter() {
+            envs.insert(var.to_string(), options.to_string());
+        }
+        envs.insert(
+            defs::BRANCHES_SHM_ENV_VAR.to_string(),
+            branches.get_id().to_string(),
+        );
+
+        let fd = PipeFd::new(&input_file);
+        let forksrv = Some(Forksrv::new(
+            &socket_path,
+            &(main_bin, main_args),
+            &envs,
+            fd.as_raw_fd(),
+            is_stdin,
+            &sanitizers,
+            time_limit,
+            mem_limit,
+            config::FORKSRV_INIT_TIMEOUT,
+            None,
+        ));
+
+        Self {
+            branches,
+            fd,
+            forksrv,
+            envs,
+            track,
+            track_path,
+            tmp_dir,
+            is_stdin,
+        }
+    }
+
+    fn write_test(&mut self, buf: &Vec<u8>) {
+        self.fd.write_buf(buf);
+        if self.is_stdin {
+            self.fd.rewind();
+        }
+    }
+
+    // Get the status and (edge, bucket of hit count) of the input.
+    pub fn run(&mut self, buf: &Vec<u8>) -> (StatusType, Vec<(usize, u8)>) {
+        self.write_test(buf);
+        self.branches.clear_trace();
+        let status = match self.forksrv {
+            Some(ref mut fs) => fs.run(),
+            None => StatusType::Error,
+        };
+        (status, self.branches.get_path())
+    }
+
+    pub fn has_track(&self) -> bool {
+        self.track.is_some()
+    }
+
+    // Get the conditions of the input with the track program.
+    pub fn track(&mut self, buf: &Vec<u8>) -> Vec<CondStmt> {
+        let track = match self.track {
+            Some(ref t) => t.clone(),
+            None => return vec![],
+        };
+        self.write_test(buf);
+        // Do not read the one of the last input if it crashes before logging.
+        let _ = fs::remove_file(&self.track_path);
+
+        let mut envs = self.envs.clone();
+        envs.insert(defs::TRACK_OUTPUT_VAR.to_string(), self.track_path.clone());
+        let mut child = Command::new(&track.0)
+            .args(&track.1)
+            .stdin(Stdio::null())
+            .env_clear()
+            .envs(&envs)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .mem_limit(config::MEM_LIMIT_TRACK)
+            .setsid()
+            .pipe_stdin(self.fd.as_raw_fd(), self.is_stdin)
+            .spawn()
+            .expect("Could not run track program");
+        let timeout = time::Duration::from_secs(config::TIME_LIMIT_TRACK);
+        if child.wait_timeout(timeout).unwrap().is_none() {
+            child.kill().expect("Could not send kill signal to child.");
+            child.wait().expect("Error during waiting for child.");
+            warn!("Timeout while tracking!");
+        }
+
+        track::load_track_data(Path::new(&self.track_path), 0, 0, false, false, buf).0
+    }
+}
+
+impl Drop for TraceRunner {
+    fn drop(&mut self) {
+        // Stop the fork server before removing its socket.
+        self.forksrv = None;
+        tmpfs::clear_tmpfs_dir(&self.tmp_dir);
+    }
+}
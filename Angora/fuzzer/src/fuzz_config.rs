@@ -0,0 +1,246 @@
+// The tuning knobs that can be changed without recompiling,
+// from a TOML file (--config) and `key=value` overrides (--set).
+// The constants in `angora_common::config` are the defaults.
+use crate::stop::StopCriteria;
+use angora_common::{config, defs};
+use serde_derive::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+// The command-line options of the fuzzer, built in bin/fuzzer.rs.
+#[derive(Debug, Clone)]
+pub struct FuzzOpt {
+    pub mode: String,
+    pub in_dir: String,
+    pub out_dir: String,
+    pub track_target: String,
+    pub pargs: Vec<String>,
+    pub num_jobs: usize,
+    pub mem_limit: u64,
+    pub time_limit: u64,
+    pub search_method: String,
+    pub sync_afl: bool,
+    pub enable_afl: bool,
+    pub enable_exploitation: bool,
+    pub enable_dict: bool,
+    pub custom_mutator: Option<String>,
+    pub enable_focus_havoc: bool,
+    pub shm_input: bool,
+    pub shm_input_file: bool,
+    pub net: Option<String>,
+    pub net_response: bool,
+    pub arg_template: Option<String>,
+    pub env_templates: Vec<String>,
+    pub queue_policy: String,
+    // minutes
+    pub checkpoint_interval: u64,
+    pub triage_crashes: bool,
+    // The sanitizers in the program besides the detected ones.
+    pub sanitizers: Vec<String>,
+    pub target: Option<String>,
+    // minutes
+    pub revive_interval: u64,
+    pub config_file: Option<String>,
+    pub config_overrides: Vec<String>,
+    pub stop_criteria: StopCriteria,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // resources
+    pub max_input_len: usize,
+    pub time_limit_track: u64,
+    pub mem_limit_track: u64,
+    pub forksrv_init_timeout: u64,
+    pub net_wait_time: u64,
+    pub tmout_skip: usize,
+    pub long_fuzz_time: usize,
+    pub max_invariable_num: usize,
+    pub prefer_fast_cond: bool,
+    pub input_cache_size: usize,
+    pub revive_max_times: usize,
+
+    // search
+    pub enable_det_mutation: bool,
+    pub max_search_exec_num: usize,
+    pub max_exploit_exec_num: usize,
+    pub max_num_minimal_optima_round: usize,
+    pub gd_escape_ratio: f64,
+    pub bonus_exec_num: usize,
+
+    // afl
+    pub enable_input_len_exploration: bool,
+    pub enable_random_len: bool,
+    pub enable_micro_random_len: bool,
+    pub mutate_arith_max: u32,
+    pub random_len_num: usize,
+    pub max_havoc_flip_times: usize,
+    pub max_splice_times: usize,
+    pub max_custom_mutator_times: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_input_len: config::MAX_INPUT_LEN,
+            time_limit_track: config::TIME_LIMIT_TRACK,
+            mem_limit_track: config::MEM_LIMIT_TRACK,
+            forksrv_init_timeout: config::FORKSRV_INIT_TIMEOUT,
+            net_wait_time: config::NET_WAIT_TIME,
+            tmout_skip: config::TMOUT_SKIP,
+            long_fuzz_time: config::LONG_FUZZ_TIME,
+            max_invariable_num: config::MAX_INVARIABLE_NUM,
+            prefer_fast_cond: config::PREFER_FAST_COND,
+            input_cache_size: config::INPUT_CACHE_SIZE,
+            revive_max_times: config::REVIVE_MAX_TIMES,
+
+            enable_det_mutation: config::ENABLE_DET_MUTATION,
+            max_search_exec_num: config::MAX_SEARCH_EXEC_NUM,
+            max_exploit_exec_num: config::MAX_EXPLOIT_EXEC_NUM,
+            max_num_minimal_optima_round: config::MAX_NUM_MINIMAL_OPTIMA_ROUND,
+            gd_escape_ratio: config::GD_ESCAPE_RATIO,
+            bonus_exec_num: config::BONUS_EXEC_NUM,
+
+            enable_input_len_exploration: config::ENABLE_INPUT_LEN_EXPLORATION,
+            enable_random_len: config::ENABLE_RANDOM_LEN,
+            enable_micro_random_len: config::ENABLE_MICRO_RANDOM_LEN,
+            mutate_arith_max: config::MUTATE_ARITH_MAX,
+            random_len_num: config::RANDOM_LEN_NUM,
+            max_havoc_flip_times: config::MAX_HAVOC_FLIP_TIMES,
+            max_splice_times: config::MAX_SPLICE_TIMES,
+            max_custom_mutator_times: config::MAX_CUSTOM_MUTATOR_TIMES,
+        }
+    }
+}
+
+impl Config {
+    // The file first, then the overrides in order.
+    pub fn parse(file: Option<&str>, overrides: &[String]) -> Result<Self, String> {
+        let mut table = match file {
+            Some(content) => match toml::from_str::<toml::Value>(content) {
+                Ok(toml::Value::Table(t)) => t,
+                Ok(_) => return Err("not a table".to_string()),
+                Err(e) => return Err(e.to_string()),
+            },
+            None => toml::value::Table::new(),
+        };
+        for o in overrides {
+            let mut kv = o.splitn(2, '=');
+            let (key, val) = match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) => (k.trim(), v.trim()),
+                _ => return Err(format!("{} is not KEY=VALUE", o)),
+            };
+            // Take it as a string if it is not a TOML value.
+            let val = toml::from_str::<toml::Value>(&format!("v = {}", val))
+                .ok()
+                .and_then(|t| t.get("v").cloned())
+                .unwrap_or_else(|| toml::Value::String(val.to_string()));
+            table.insert(key.to_string(), val);
+        }
+        let config: Self = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // The values that the searches and the executor can not run with,
+    // e.g. gen_range(0, 0) panics.
+    pub fn validate(&self) -> Result<(), String> {
+        let at_least_one = [
+            ("max_input_len", self.max_input_len as u64),
+            ("time_limit_track", self.time_limit_track),
+            ("forksrv_init_timeout", self.forksrv_init_timeout),
+            ("net_wait_time", self.net_wait_time),
+            ("tmout_skip", self.tmout_skip as u64),
+            ("max_invariable_num", self.max_invariable_num as u64),
+            ("max_search_exec_num", self.max_search_exec_num as u64),
+            ("mutate_arith_max", self.mutate_arith_max as u64),
+            ("random_len_num", self.random_len_num as u64),
+        ];
+        for (key, val) in at_least_one.iter() {
+            if *val == 0 {
+                return Err(format!("{} should be at least 1", key));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.gd_escape_ratio) {
+            return Err(format!(
+                "gd_escape_ratio should be between 0 and 1, not {}",
+                self.gd_escape_ratio
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn load(file: Option<&str>, overrides: &[String]) -> Self {
+        let content = file.map(|f| match fs::read_to_string(f) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Could not read config file {}: {:?}", f, e);
+                panic!();
+            },
+        });
+        match Self::parse(content.as_deref(), overrides) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Invalid config: {}", e);
+                panic!();
+            },
+        }
+    }
+
+    // The effective config, to know how the run was tuned.
+    pub fn save(&self, out_dir: &Path) {
+        let content = toml::to_string_pretty(self).expect("Could not serialize config.");
+        if let Err(e) = fs::write(out_dir.join(defs::CONFIG_FILE), content) {
+            warn!("Could not write config file: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let c = Config::parse(None, &[]).unwrap();
+        assert_eq!(c.max_input_len, config::MAX_INPUT_LEN);
+
+        let file = "max_input_len = 100\nenable_det_mutation = false\n";
+        let overrides = vec![
+            "max_input_len=200".to_string(),
+            "gd_escape_ratio = 0.5".to_string(),
+        ];
+        let c = Config::parse(Some(file), &overrides).unwrap();
+        assert_eq!(c.max_input_len, 200);
+        assert!(!c.enable_det_mutation);
+        assert_eq!(c.gd_escape_ratio, 0.5);
+        assert_eq!(c.max_search_exec_num, config::MAX_SEARCH_EXEC_NUM);
+
+        assert!(Config::parse(Some("no_such_key = 1"), &[]).is_err());
+        assert!(Config::parse(None, &["max_input_len".to_string()]).is_err());
+        assert!(Config::parse(None, &["max_input_len=abc".to_string()]).is_err());
+
+        // The dumped one can be loaded again.
+        let dumped = toml::to_string_pretty(&c).unwrap();
+        assert_eq!(Config::parse(Some(&dumped), &[]).unwrap().max_input_len, 200);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert!(Config::default().validate().is_ok());
+        for o in &[
+            "mutate_arith_max=0",
+            "random_len_num=0",
+            "max_input_len=0",
+            "time_limit_track=0",
+            "gd_escape_ratio=1.5",
+            "gd_escape_ratio=-0.1",
+        ] {
+            let e = Config::parse(None, &[o.to_string()]).unwrap_err();
+            assert!(e.starts_with(o.split('=').next().unwrap()), "{}", e);
+        }
+        assert!(Config::parse(None, &["gd_escape_ratio=1.0".to_string()]).is_ok());
+    }
+}
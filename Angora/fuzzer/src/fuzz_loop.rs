@@ -73,14 +73,20 @@ pub fn fuzz_loop(
             let handler = SearchHandler::new(running.clone(), &mut executor, &mut cond, buf);
             match fuzz_type {
                 FuzzType::ExploreFuzz => {
-                    if handler.cond.is_time_expired() {
+                    if handler.cond.is_time_expired(handler.executor.cmd.config.long_fuzz_time) {
+                        handler.cond.next_state();
+                    }
+                    if handler.cond.state.is_det() && !handler.executor.cmd.config.enable_det_mutation {
                         handler.cond.next_state();
                     }
                     if handler.cond.state.is_one_byte() {
+                        handler.executor.stage = "one_byte";
                         OneByteFuzz::new(handler).run();
                     } else if handler.cond.state.is_det() {
+                        handler.executor.stage = "det";
                         DetFuzz::new(handler).run();
                     } else {
+                        handler.executor.stage = search_method.name();
                         match search_method {
                             SearchMethod::Gd => {
                                 GdSearch::new(handler, enable_dict).run(&mut thread_rng());
@@ -98,6 +104,7 @@ pub fn fuzz_loop(
                     }
                 },
                 FuzzType::ExploitFuzz => {
+                    handler.executor.stage = "exploit";
                     if handler.cond.state.is_one_byte() {
                         let mut fz = OneByteFuzz::new(handler);
                         fz.run();
@@ -110,9 +117,11 @@ pub fn fuzz_loop(
                     AFLFuzz::new(handler, enable_dict).run();
                 },
                 FuzzType::LenFuzz => {
+                    handler.executor.stage = "len";
                     LenFuzz::new(handler).run();
                 },
                 FuzzType::CmpFnFuzz => {
+                    handler.executor.stage = "cmpfn";
                     FnFuzz::new(handler).run();
                 },
                 FuzzType::OtherFuzz => {
@@ -121,5 +130,6 @@ pub fn fuzz_loop(
             }
         }
         depot.update_entry(cond);
+        executor.save_pending_outputs();
     }
 }
@@ -4,7 +4,7 @@ use chrono::prelude::Local;
 use std::{
     collections::HashMap,
     fs,
-    io::prelude::*,
+    io::{self, prelude::*},
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
@@ -13,45 +13,60 @@ use std::{
     thread, time,
 };
 
-use crate::{bind_cpu, branches, check_dep, command, depot, executor, fuzz_loop, stats, search};
+use crate::{
+    arg_template::ArgTemplate, bind_cpu, branches, check_dep, checkpoint::Checkpoint, command, depot, executor,
+    fuzz_config::{self, FuzzOpt},
+    fuzz_loop, search, stats,
+    stop::{self, StopReason, StopWatch},
+    triage,
+};
 use ctrlc;
 use libc;
 use pretty_env_logger;
 
-pub fn fuzz_main(
-    mode: &str,
-    in_dir: &str,
-    out_dir: &str,
-    track_target: &str,
-    pargs: Vec<String>,
-    num_jobs: usize,
-    mem_limit: u64,
-    time_limit: u64,
-    search_method: &str,
-    sync_afl: bool,
-    enable_afl: bool,
-    enable_exploitation: bool,
-    enable_dict: bool,
-) {
+pub fn fuzz_main(opt: FuzzOpt) -> i32 {
     pretty_env_logger::init();
 
-    let (seeds_dir, angora_out_dir) = initialize_directories(in_dir, out_dir, sync_afl);
-    let command_option = command::CommandOpt::new(
-        mode,
-        track_target,
-        pargs,
-        &angora_out_dir,
-        search_method,
-        mem_limit,
-        time_limit,
-        enable_afl,
-        enable_exploitation,
-        enable_dict,
-    );
+    let config = fuzz_config::Config::load(opt.config_file.as_deref(), &opt.config_overrides);
+    let (seeds_dir, angora_out_dir, checkpoint) =
+        initialize_directories(&opt.in_dir, &opt.out_dir, opt.sync_afl);
+    config.save(&angora_out_dir);
+    let net = opt.net.as_ref().map(|n| match executor::NetOpt::parse(n, opt.net_response, config.net_wait_time) {
+        Ok(net) => net,
+        Err(e) => {
+            error!("Invalid --net: {}", e);
+            panic!();
+        },
+    });
+    let arg_template = if opt.arg_template.is_some() || !opt.env_templates.is_empty() {
+        match ArgTemplate::parse(opt.arg_template.as_deref().unwrap_or(""), &opt.env_templates) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                error!("Invalid argument template: {}", e);
+                panic!();
+            },
+        }
+    } else {
+        None
+    };
+    let command_option =
+        command::CommandOpt::new(&opt, &angora_out_dir, net, arg_template, config.clone());
     info!("{:?}", command_option);
-    check_dep::check_dep(in_dir, out_dir, &command_option);
+    check_dep::check_dep(&opt.in_dir, &opt.out_dir, &command_option);
+    if let Some(ref net) = command_option.net {
+        check_net_port(net, &command_option, &opt);
+    }
+    if command_option.is_deferred && command_option.arg_template.is_some() {
+        error!("The arguments from the input are set before main, but the fork server is deferred until main has parsed them.");
+        panic!();
+    }
 
-    let depot = Arc::new(depot::Depot::new(seeds_dir, &angora_out_dir));
+    let depot = Arc::new(depot::Depot::new(
+        seeds_dir,
+        &angora_out_dir,
+        depot::parse_queue_policy(&opt.queue_policy),
+        &config,
+    ));
     info!("{:?}", depot.dirs);
 
     let dict = Arc::new(RwLock::new(search::interesting_val::Dict::default()));
@@ -69,7 +84,20 @@ pub fn fuzz_main(
         dict.clone(),
     );
 
-    depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+    let resumed = checkpoint.is_some();
+    if let Some(c) = checkpoint {
+        c.restore(&depot, &global_branches, &stats);
+        info!(
+            "Resume from checkpoint, {} inputs in the queue.",
+            depot.num_inputs.load(Ordering::Relaxed)
+        );
+    }
+    if let Some(ref t) = opt.target {
+        depot.set_targets(depot::Targets::load(Path::new(t)));
+    }
+    if !resumed {
+        depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+    }
 
     if depot.empty() {
         error!("Failed to find any branches during dry run.");
@@ -82,7 +110,7 @@ pub fn fuzz_main(
     }
 
     let (handles, child_count) = init_cpus_and_run_fuzzing_threads(
-        num_jobs,
+        opt.num_jobs,
         &running,
         &command_option,
         &global_branches,
@@ -90,47 +118,107 @@ pub fn fuzz_main(
         &stats,
         &dict,
     );
+    let triage_handle = if opt.triage_crashes {
+        let r = running.clone();
+        let cmd = command_option.specify(opt.num_jobs + 1);
+        let d = depot.clone();
+        let b = global_branches.clone();
+        let s = stats.clone();
+        let dict = dict.clone();
+        Some(thread::spawn(move || triage::triage_loop(r, cmd, d, b, s, dict)))
+    } else {
+        None
+    };
 
-    let log_file = match fs::File::create(angora_out_dir.join(defs::ANGORA_LOG_FILE)) {
+    let log_file = match open_log_file(&angora_out_dir.join(defs::ANGORA_LOG_FILE), resumed) {
         Ok(a) => a,
         Err(e) => {
             error!("FATAL: Could not create log file: {:?}", e);
             panic!();
         }
     };
-    let dlog_file = match fs::File::create(angora_out_dir.join(defs::ANGORA_DLOG_FILE)) {
+    let dlog_file = match open_log_file(&angora_out_dir.join(defs::ANGORA_DLOG_FILE), resumed) {
         Ok(a) => a,
         Err(e) => {
             error!("FATAL: Could not create log file: {:?}", e);
             panic!();
         }
     };
-    main_thread_sync_and_log(
+    let reason = main_thread_sync_and_log(
         log_file,
         dlog_file,
-        out_dir,
-        sync_afl,
+        &opt.out_dir,
+        opt.sync_afl,
         running.clone(),
         &mut executor,
         &depot,
         &global_branches,
         &stats,
         child_count,
+        &angora_out_dir,
+        opt.checkpoint_interval,
+        opt.revive_interval,
+        StopWatch::new(opt.stop_criteria.clone()),
     );
+    // Stop the fuzzing threads if it ends by the criteria.
+    running.store(false, Ordering::SeqCst);
 
+    let mut has_error = false;
     for handle in handles {
         if handle.join().is_err() {
             error!("Error happened in fuzzing thread!");
+            has_error = true;
+        }
+    }
+    if let Some(handle) = triage_handle {
+        if handle.join().is_err() {
+            error!("Error happened in triage thread!");
         }
     }
 
+    Checkpoint::save(&angora_out_dir, &depot, &global_branches, &stats);
+
+    let s = stats.read().unwrap();
+    let progress = s.progress(global_branches.get_covered_branches());
+    let code = stop::exit_code(reason, has_error, progress.num_crashes);
+    let chart = serde_json::to_value(&*s).unwrap_or_default();
+    stop::write_summary(&angora_out_dir, reason, code, &progress, chart);
+    info!("Stop: {:?}, exit code: {}", reason, code);
+
     match fs::remove_file(&fuzzer_stats) {
         Ok(_) => (),
         Err(e) => warn!("Could not remove fuzzer stats file: {:?}", e),
     };
+    code
 }
 
-fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathBuf, PathBuf) {
+// The executors run at the same time, except the dry run of the seeds,
+// so they need their own ports, and so does a deferred fork server that listens before it forks.
+fn check_net_port(
+    net: &executor::NetOpt,
+    cmd: &command::CommandOpt,
+    opt: &FuzzOpt,
+) {
+    if executor::has_port_arg(&cmd.main.1) {
+        // The triage executor has the last id.
+        if net.port as usize + opt.num_jobs + 1 > u16::MAX as usize {
+            error!("The port {} is too large for {} jobs", net.port, opt.num_jobs);
+            panic!();
+        }
+    } else if opt.num_jobs > 1 || opt.sync_afl || opt.triage_crashes || cmd.is_deferred {
+        error!(
+            "--net with -j N, --sync_afl, --triage_crashes or a deferred fork server needs {} in the arguments of the program, for the port of each executor",
+            executor::PORT_ARG
+        );
+        panic!();
+    }
+}
+
+fn initialize_directories(
+    in_dir: &str,
+    out_dir: &str,
+    sync_afl: bool,
+) -> (PathBuf, PathBuf, Option<Checkpoint>) {
     let angora_out_dir = if sync_afl {
         gen_path_afl(out_dir)
     } else {
@@ -143,6 +231,14 @@ fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathB
     }
 
     let out_dir = &angora_out_dir;
+    if restart {
+        // Continue in the same directory.
+        if let Some(c) = Checkpoint::load(out_dir) {
+            let seeds_dir = out_dir.join(defs::INPUTS_DIR);
+            return (seeds_dir, angora_out_dir, Some(c));
+        }
+    }
+
     let seeds_dir = if restart {
         let orig_out_dir = out_dir.with_extension(Local::now().to_rfc3339());
         fs::rename(&out_dir, orig_out_dir.clone()).unwrap();
@@ -152,7 +248,15 @@ fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathB
         PathBuf::from(in_dir)
     };
 
-    (seeds_dir, angora_out_dir)
+    (seeds_dir, angora_out_dir, None)
+}
+
+fn open_log_file(path: &Path, append: bool) -> io::Result<fs::File> {
+    if append {
+        fs::OpenOptions::new().create(true).append(true).open(path)
+    } else {
+        fs::File::create(path)
+    }
 }
 
 fn gen_path_afl(out_dir: &str) -> PathBuf {
@@ -238,7 +342,12 @@ fn main_thread_sync_and_log(
     global_branches: &Arc<branches::GlobalBranches>,
     stats: &Arc<RwLock<stats::ChartStats>>,
     child_count: Arc<AtomicUsize>,
-) {
+    angora_out_dir: &Path,
+    checkpoint_interval: u64,
+    revive_interval: u64,
+    mut stop_watch: StopWatch,
+) -> StopReason {
+    let mut last_checkpoint = time::Instant::now();
     let mut last_explore_num = stats.read().unwrap().get_explore_num();
     let sync_dir = Path::new(out_dir);
     let mut synced_ids = HashMap::new();
@@ -255,22 +364,46 @@ fn main_thread_sync_and_log(
             depot::sync_afl(executor, running.clone(), sync_dir, &mut synced_ids);
             sync_counter = 12;
         }
+        // e.g. the crashing seeds
+        executor.save_pending_outputs();
+
+        if revive_interval > 0 {
+            let n = depot.revive_expired(revive_interval * 60);
+            if n > 0 {
+                info!("Revive {} discarded conditions.", n);
+            }
+        }
 
         show_stats(&mut log_file, depot, global_branches, stats,
                    &mut dlog_file, executor);
+        if checkpoint_interval > 0
+            && last_checkpoint.elapsed().as_secs() >= checkpoint_interval * 60
+        {
+            Checkpoint::save(angora_out_dir, depot, global_branches, stats);
+            last_checkpoint = time::Instant::now();
+        }
+        let progress = stats
+            .read()
+            .unwrap()
+            .progress(global_branches.get_covered_branches());
+        if let Some(reason) = stop_watch.check(&progress) {
+            info!("Stop fuzzing: {:?}", reason);
+            return reason;
+        }
         if Arc::strong_count(&child_count) == 1 {
             let s = stats.read().unwrap();
             let cur_explore_num = s.get_explore_num();
             if cur_explore_num == 0 {
                 warn!("There is none constraint in the seeds, please ensure the inputs are vaild in the seed directory, or the program is ran correctly, or the read functions have been marked as source.");
-                break;
+                return StopReason::NoConstraint;
             } else {
                 if cur_explore_num == last_explore_num {
                     info!("Solve all constraints!!");
-                    break;
+                    return StopReason::AllSolved;
                 }
                 last_explore_num = cur_explore_num;
             }
         }
     }
+    StopReason::Interrupted
 }
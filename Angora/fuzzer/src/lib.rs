@@ -5,15 +5,21 @@ extern crate log;
 #[macro_use]
 extern crate derive_more;
 
+mod arg_template;
 mod branches;
+mod checkpoint;
+mod cmin;
 pub mod cond_stmt;
 mod depot;
 pub mod executor;
+mod multi_input;
 mod mut_input;
+mod mutator;
 mod search;
 mod stats;
 pub mod track;
 
+mod fuzz_config;
 mod fuzz_loop;
 mod fuzz_main;
 mod fuzz_type;
@@ -21,6 +27,12 @@ mod fuzz_type;
 mod bind_cpu;
 mod check_dep;
 mod command;
+mod stop;
+mod tmin;
 mod tmpfs;
+mod triage;
 
-pub use crate::fuzz_main::fuzz_main;
+pub use crate::{
+    cmin::cmin_main, fuzz_config::FuzzOpt, fuzz_main::fuzz_main, stop::StopCriteria,
+    tmin::tmin_main,
+};
@@ -0,0 +1,101 @@
+// Inputs of the programs that read several files, e.g. `prog @@1 @@2`.
+// A test case is a container of the parts, so it is mutated and tracked as a whole:
+// each part but the last is its length (u32, little endian) followed by its bytes,
+// and the last part is the rest. The offsets in the part files are mapped back
+// to the container by the track runtime (ANGORA_INPUT_PARTS).
+use byteorder::{ByteOrder, LittleEndian};
+use std::{fs, io, ops::Range, path::Path};
+
+const LEN_SIZE: usize = 4;
+
+// The number N of a placeholder `@@N`, starting from 1.
+pub fn part_of_arg(arg: &str) -> Option<usize> {
+    if !arg.starts_with("@@") {
+        return None;
+    }
+    arg[2..].parse::<usize>().ok().filter(|&n| n > 0)
+}
+
+// The number of parts, 0 if the input is a single file.
+pub fn num_parts(args: &[String]) -> usize {
+    args.iter().filter_map(|a| part_of_arg(a)).max().unwrap_or(0)
+}
+
+pub fn part_file(file: &str, n: usize) -> String {
+    format!("{}.{}", file, n)
+}
+
+// Where the parts are in the container.
+// A mutated length is cut at the end of the container, and the rest parts are empty.
+pub fn split(buf: &[u8], num: usize) -> Vec<Range<usize>> {
+    let mut parts = Vec::with_capacity(num);
+    let mut pos = 0;
+    for i in 0..num {
+        if i + 1 == num {
+            parts.push(pos..buf.len());
+            break;
+        }
+        let begin = (pos + LEN_SIZE).min(buf.len());
+        let len = if begin - pos == LEN_SIZE {
+            LittleEndian::read_u32(&buf[pos..begin]) as usize
+        } else {
+            0
+        };
+        let end = begin.saturating_add(len).min(buf.len());
+        parts.push(begin..end);
+        pos = end;
+    }
+    parts
+}
+
+pub fn pack(parts: &[Vec<u8>]) -> Vec<u8> {
+    let mut buf = vec![];
+    for (i, p) in parts.iter().enumerate() {
+        if i + 1 < parts.len() {
+            let mut len = [0; LEN_SIZE];
+            LittleEndian::write_u32(&mut len, p.len() as u32);
+            buf.extend_from_slice(&len);
+        }
+        buf.extend_from_slice(p);
+    }
+    buf
+}
+
+// A seed can be a directory with the files of the parts, named 1, 2, ..
+pub fn pack_dir(dir: &Path, num: usize) -> io::Result<Vec<u8>> {
+    let parts = (1..=num)
+        .map(|n| {
+            let f = dir.join(n.to_string());
+            if f.is_file() {
+                fs::read(f)
+            } else {
+                Ok(vec![])
+            }
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(pack(&parts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_input() {
+        let args = vec!["-c".to_string(), "@@2".to_string(), "@@1".to_string()];
+        assert_eq!(num_parts(&args), 2);
+        assert_eq!(part_of_arg("@@"), None);
+        assert_eq!(part_of_arg("@@0"), None);
+
+        let parts = vec![b"cfg".to_vec(), vec![], b"data".to_vec()];
+        let buf = pack(&parts);
+        let ranges = split(&buf, 3);
+        assert_eq!(ranges, vec![4..7, 11..11, 11..15]);
+        let got: Vec<Vec<u8>> = ranges.iter().map(|r| buf[r.clone()].to_vec()).collect();
+        assert_eq!(got, parts);
+
+        // The length is too large, or the container is too short.
+        assert_eq!(split(&[9, 0, 0, 0, b'a'], 2), vec![4..5, 5..5]);
+        assert_eq!(split(&[1, 0], 2), vec![2..2, 2..2]);
+    }
+}
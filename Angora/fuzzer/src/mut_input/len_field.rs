@@ -0,0 +1,163 @@
+// Length fields in the input: the bytes whose value is compared with
+// the number of units the program read from `data_begin`.
+// If a mutation inserts or removes bytes inside the data, we rewrite the field,
+// so that the new input will not be rejected by a trivial size check.
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LenField {
+    pub begin: u32,
+    pub width: u32,
+    pub data_begin: u32,
+    pub unit: u32,
+    pub big_endian: bool,
+}
+
+// (position, size change) of an insertion or deletion.
+pub type LenEdit = (u32, i64);
+
+impl LenField {
+    pub fn read(&self, buf: &[u8]) -> Option<u64> {
+        let begin = self.begin as usize;
+        let end = begin + self.width as usize;
+        if self.width == 0 || self.width > 8 || end > buf.len() {
+            return None;
+        }
+        let bytes = &buf[begin..end];
+        if self.big_endian {
+            Some(BigEndian::read_uint(bytes, self.width as usize))
+        } else {
+            Some(LittleEndian::read_uint(bytes, self.width as usize))
+        }
+    }
+
+    pub fn write(&self, buf: &mut [u8], val: u64) -> bool {
+        let begin = self.begin as usize;
+        let end = begin + self.width as usize;
+        if self.width == 0 || self.width > 8 || end > buf.len() {
+            return false;
+        }
+        if self.width < 8 && val >= (1 << (self.width * 8)) {
+            return false;
+        }
+        let bytes = &mut buf[begin..end];
+        if self.big_endian {
+            BigEndian::write_uint(bytes, val, self.width as usize);
+        } else {
+            LittleEndian::write_uint(bytes, val, self.width as usize);
+        }
+        true
+    }
+}
+
+// Follow a field through the edits, and get its new location and value.
+fn apply_edits(field: &LenField, val: u64, edits: &[LenEdit]) -> Option<(LenField, u64)> {
+    let mut field = *field;
+    let mut val = val as i64;
+    let unit = field.unit as i64;
+    if unit == 0 {
+        return None;
+    }
+
+    for &(pos, delta) in edits {
+        let pos = pos as i64;
+        let begin = field.begin as i64;
+        let width = field.width as i64;
+        let data_begin = field.data_begin as i64;
+        let data_end = data_begin + val * unit;
+        // [pos, edit_end) is removed if delta < 0
+        let edit_end = if delta < 0 { pos - delta } else { pos };
+
+        // The field itself is touched.
+        if (delta < 0 && pos < begin + width && edit_end > begin)
+            || (delta > 0 && pos > begin && pos < begin + width)
+        {
+            return None;
+        }
+
+        if pos >= data_begin && edit_end <= data_end && pos < data_end {
+            if delta % unit != 0 {
+                return None;
+            }
+            val += delta / unit;
+        }
+
+        if edit_end <= begin {
+            field.begin = (begin + delta) as u32;
+        }
+        if edit_end <= data_begin {
+            field.data_begin = (data_begin + delta) as u32;
+        }
+    }
+
+    if val < 0 {
+        return None;
+    }
+    Some((field, val as u64))
+}
+
+// `orig` is the buffer before mutation, and `buf` is the mutated one.
+pub fn fix_len_fields(orig: &[u8], buf: &mut [u8], fields: &[LenField], edits: &[LenEdit]) {
+    if edits.is_empty() {
+        return;
+    }
+    for f in fields {
+        if let Some(val) = f.read(orig) {
+            if let Some((new_f, new_val)) = apply_edits(f, val, edits) {
+                if new_val != val || new_f.begin != f.begin {
+                    new_f.write(buf, new_val);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_in_data() {
+        // 2 bytes BE length at 1, data starts at 3.
+        let field = LenField {
+            begin: 1,
+            width: 2,
+            data_begin: 3,
+            unit: 1,
+            big_endian: true,
+        };
+        let orig = vec![0xff, 0, 3, 1, 2, 3, 0xee];
+        let mut buf = orig.clone();
+        buf.insert(4, 9);
+        buf.insert(0, 9);
+        fix_len_fields(&orig, &mut buf, &[field], &[(4, 1), (0, 1)]);
+        assert_eq!(buf, vec![9, 0xff, 0, 4, 1, 9, 2, 3, 0xee]);
+
+        // Bytes after the data are not counted.
+        let mut buf = orig.clone();
+        buf.push(0);
+        fix_len_fields(&orig, &mut buf, &[field], &[(7, 1)]);
+        assert_eq!(buf[..orig.len()], orig[..]);
+    }
+
+    #[test]
+    fn test_delete_field() {
+        let field = LenField {
+            begin: 0,
+            width: 1,
+            data_begin: 1,
+            unit: 2,
+            big_endian: false,
+        };
+        let orig = vec![2, 1, 1, 2, 2];
+        let mut buf = vec![2, 2, 2];
+        fix_len_fields(&orig, &mut buf, &[field], &[(1, -2)]);
+        assert_eq!(buf, vec![1, 2, 2]);
+        // The field is removed.
+        assert!(apply_edits(&field, 2, &[(0, -1)]).is_none());
+        // Not aligned to the unit.
+        assert!(apply_edits(&field, 2, &[(1, -1)]).is_none());
+    }
+}
@@ -1,8 +1,10 @@
+mod len_field;
 mod mut_input;
+pub mod mut_op;
 pub mod offsets;
 mod rw;
 mod serialize;
 
 use angora_common::tag::TagSeg;
 
-pub use self::{mut_input::MutInput, rw::*, serialize::*};
+pub use self::{len_field::*, mut_input::MutInput, mut_op::*, rw::*, serialize::*};
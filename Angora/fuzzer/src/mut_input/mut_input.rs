@@ -2,7 +2,7 @@ use super::*;
 use crate::search;
 use angora_common::config;
 
-use rand::{self, distributions::Uniform, Rng};
+use rand::{self, Rng};
 
 use std::sync::{Arc, RwLock};
 use std::{fmt, u8};
@@ -11,6 +11,20 @@ use std::{cmp, u32, u64};
 
 use std::string::String;
 
+static MUT_BASED_OPS: [MutOp; 4] = [
+    MutOp::FlipBit,
+    MutOp::Arith,
+    MutOp::Interesting,
+    MutOp::RandomByte,
+];
+static MUT_BASED_OPS_DICT: [MutOp; 5] = [
+    MutOp::FlipBit,
+    MutOp::Arith,
+    MutOp::Interesting,
+    MutOp::RandomByte,
+    MutOp::OverwriteDict,
+];
+
 #[derive(Clone, Debug, Constructor)]
 struct InputMeta {
     sign: bool,
@@ -151,18 +165,31 @@ impl MutInput {
         set_bytes_by_offsets(offsets, &self.value, input);
     }
 
-    pub fn randomize_all(&mut self, enable_dict: bool, dict: Arc<RwLock<search::interesting_val::Dict>>) {
+    pub fn randomize_all(
+        &mut self,
+        enable_dict: bool,
+        dict: Arc<RwLock<search::interesting_val::Dict>>,
+        sched: &MutOpSched,
+    ) -> MutOpSet {
         let mut rng = rand::thread_rng();
-        self.randomize_all_with_weight(&mut rng, 3, enable_dict, dict);
+        self.randomize_all_with_weight(&mut rng, 3, enable_dict, dict, sched)
     }
 
-    pub fn randomize_all_with_weight<T: Rng>(&mut self, rng: &mut T, weight: u32, enable_dict: bool, dict: Arc<RwLock<search::interesting_val::Dict>>) {
+    pub fn randomize_all_with_weight<T: Rng>(
+        &mut self,
+        rng: &mut T,
+        weight: u32,
+        enable_dict: bool,
+        dict: Arc<RwLock<search::interesting_val::Dict>>,
+        sched: &MutOpSched,
+    ) -> MutOpSet {
         // 1/weight true
         let coin = rng.gen_bool(1.0 / weight as f64);
         if coin {
             self.randomize_all_uniform(rng);
+            MutOpSet::default()
         } else {
-            self.randomize_all_mut_based(rng, enable_dict, dict);
+            self.randomize_all_mut_based(rng, enable_dict, dict, sched)
         }
     }
 
@@ -170,7 +197,13 @@ impl MutInput {
         rng.fill_bytes(&mut self.value);
     }
 
-    pub fn randomize_all_mut_based<T: Rng>(&mut self, rng: &mut T, enable_dict: bool, dict: Arc<RwLock<search::interesting_val::Dict>>) {
+    pub fn randomize_all_mut_based<T: Rng>(
+        &mut self,
+        rng: &mut T,
+        enable_dict: bool,
+        dict: Arc<RwLock<search::interesting_val::Dict>>,
+        sched: &MutOpSched,
+    ) -> MutOpSet {
         let entry_len = self.len() as u32;
         let byte_len = self.val_len() as u32;
         assert!(byte_len > 0 && entry_len > 0);
@@ -183,48 +216,45 @@ impl MutInput {
             1 + rng.gen_range(0, 256)
         };
 
-        //let choice_range = Uniform::new(0, 6);
-        let choice_range = if enable_dict {
-            Uniform::new(0, 7)
-        }
-        else {
-            Uniform::new(0, 6)
+        let ops: &[MutOp] = if enable_dict {
+            &MUT_BASED_OPS_DICT
+        } else {
+            &MUT_BASED_OPS
         };
-        
+        let dist = sched.distribution(ops);
+        let mut used = MutOpSet::default();
+
         for _ in 0..use_stacking {
-            match rng.sample(choice_range) {
-                0 | 1 => {
+            let op = MutOpSched::pick(ops, &dist, rng);
+            used.add(op);
+            match op {
+                MutOp::FlipBit => {
                     // flip bit
                     let byte_idx: u32 = rng.gen_range(0, byte_len);
                     let bit_idx: u32 = rng.gen_range(0, 8);
                     self.value[byte_idx as usize] ^= 128 >> bit_idx;
-                }
-                2 => {
-                    //add
-                    let entry_idx: u32 = rng.gen_range(0, entry_len);
-                    let v: u32 = rng.gen_range(1, config::MUTATE_ARITH_MAX);
-                    self.update(entry_idx as usize, true, v as u64);
-                }
-                3 => {
-                    // sub
+                },
+                MutOp::Arith => {
+                    // add or sub
                     let entry_idx: u32 = rng.gen_range(0, entry_len);
                     let v: u32 = rng.gen_range(1, config::MUTATE_ARITH_MAX);
-                    self.update(entry_idx as usize, false, v as u64);
-                }
-                4 => {
+                    let direction: bool = rng.gen();
+                    self.update(entry_idx as usize, direction, v as u64);
+                },
+                MutOp::Interesting => {
                     // set interesting value
                     let entry_idx: u32 = rng.gen_range(0, entry_len as u32);
                     let n = self.get_entry_len(entry_idx as usize);
                     let vals = search::get_interesting_bytes(n);
                     let wh = rng.gen_range(0, vals.len() as u32);
                     self.set(entry_idx as usize, vals[wh as usize]);
-                }
-                5 => {
+                },
+                MutOp::RandomByte => {
                     // random byte
                     let byte_idx: u32 = rng.gen_range(0, byte_len);
                     self.value[byte_idx as usize] = rng.gen();
-                }
-                6 => {
+                },
+                MutOp::OverwriteDict => {
                     // replace bytes with dict
                     let d = match dict.read() {
                         Ok(guard) => guard,
@@ -268,10 +298,12 @@ impl MutInput {
                     let size = word.len() as usize;
                     let info = &self.meta[entry_idx];
                     set_word_in_buf(&mut self.value, info.offset, std::cmp::min(info.size, size), word);
-                }
-                _ => {}
+                },
+                // They change the length, so they are not in MUT_BASED_OPS (see search/afl.rs).
+                MutOp::DeleteBytes | MutOp::InsertBytes | MutOp::InsertDict => unreachable!(),
             }
         }
+        used
     }
 }
 
@@ -0,0 +1,232 @@
+// Adaptive scheduling of the havoc operators (MOpt-style).
+// Each fuzzing thread samples operators from its own copy of the distribution
+// and counts how often they were used and led to a new path.
+// The counters are merged into `ChartStats` after every round,
+// where the distribution is re-computed and handed back to the thread.
+
+use angora_common::config;
+use rand::{distributions::WeightedIndex, prelude::*};
+use serde_derive::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MutOp {
+    FlipBit,
+    Arith,
+    Interesting,
+    RandomByte,
+    DeleteBytes,
+    InsertBytes,
+    InsertDict,
+    OverwriteDict,
+}
+
+pub const MUT_OP_NUM: usize = MutOp::OverwriteDict as usize + 1;
+static MUT_OP_NAME: [&str; MUT_OP_NUM] = [
+    "flip_bit",
+    "arith",
+    "interesting",
+    "random_byte",
+    "delete_bytes",
+    "insert_bytes",
+    "insert_dict",
+    "overwrite_dict",
+];
+// The weights of the original uniform choice: flip and arith had two slots.
+static MUT_OP_INIT_WEIGHT: [f64; MUT_OP_NUM] = [2.0, 2.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+
+impl MutOp {
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+pub fn get_mut_op_name(i: usize) -> &'static str {
+    MUT_OP_NAME[i]
+}
+
+// Operators used in one (stacked) mutation.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MutOpSet(u32);
+
+impl MutOpSet {
+    pub fn add(&mut self, op: MutOp) {
+        self.0 |= 1 << op.index();
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.0 & (1 << i) > 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct MutOpCounter {
+    pub num_used: usize,
+    pub num_finds: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MutOpSched {
+    probs: [f64; MUT_OP_NUM],
+    counters: [MutOpCounter; MUT_OP_NUM],
+}
+
+impl Default for MutOpSched {
+    fn default() -> Self {
+        Self {
+            probs: init_probs(),
+            counters: Default::default(),
+        }
+    }
+}
+
+impl MutOpSched {
+    pub fn distribution(&self, ops: &[MutOp]) -> WeightedIndex<f64> {
+        WeightedIndex::new(ops.iter().map(|op| self.probs[op.index()]))
+            .expect("Invalid mutation operator distribution.")
+    }
+
+    pub fn pick<R: Rng>(ops: &[MutOp], dist: &WeightedIndex<f64>, rng: &mut R) -> MutOp {
+        ops[dist.sample(rng)]
+    }
+
+    pub fn record(&mut self, used: MutOpSet, has_new_path: bool) {
+        if used.is_empty() {
+            return;
+        }
+        for (i, c) in self.counters.iter_mut().enumerate() {
+            if used.contains(i) {
+                c.num_used += 1;
+                if has_new_path {
+                    c.num_finds += 1;
+                }
+            }
+        }
+    }
+
+    // Hand the local counters to the global one, and take its distribution.
+    pub fn sync(&mut self, global: &mut MutOpStats) {
+        global.merge(&self.counters);
+        self.counters = Default::default();
+        self.probs = global.probs;
+    }
+}
+
+fn init_probs() -> [f64; MUT_OP_NUM] {
+    let sum: f64 = MUT_OP_INIT_WEIGHT.iter().sum();
+    let mut probs = [0.0; MUT_OP_NUM];
+    for (p, w) in probs.iter_mut().zip(MUT_OP_INIT_WEIGHT.iter()) {
+        *p = w / sum;
+    }
+    probs
+}
+
+#[derive(Clone)]
+pub struct MutOpStats {
+    probs: [f64; MUT_OP_NUM],
+    counters: [MutOpCounter; MUT_OP_NUM],
+}
+
+impl Default for MutOpStats {
+    fn default() -> Self {
+        Self {
+            probs: init_probs(),
+            counters: Default::default(),
+        }
+    }
+}
+
+impl MutOpStats {
+    fn merge(&mut self, local: &[MutOpCounter; MUT_OP_NUM]) {
+        let mut has_new = false;
+        for (g, l) in self.counters.iter_mut().zip(local.iter()) {
+            g.num_used += l.num_used;
+            g.num_finds += l.num_finds;
+            has_new |= l.num_used > 0;
+        }
+        if has_new {
+            self.update_probs();
+        }
+    }
+
+    // Bandit update: the weight of each operator is its prior weight scaled by
+    // its (smoothed) success rate. Every operator keeps a minimal probability so
+    // that it could be picked again once the program reaches other code.
+    fn update_probs(&mut self) {
+        let mut weights = [0.0; MUT_OP_NUM];
+        for (i, w) in weights.iter_mut().enumerate() {
+            let c = &self.counters[i];
+            let rate = (c.num_finds as f64 + 1.0) / (c.num_used as f64 + config::MUT_OP_PRIOR_EXEC);
+            *w = MUT_OP_INIT_WEIGHT[i] * rate;
+        }
+        let sum: f64 = weights.iter().sum();
+        let mut norm = 0.0;
+        for (p, w) in self.probs.iter_mut().zip(weights.iter()) {
+            *p = (w / sum).max(config::MUT_OP_MIN_PROB);
+            norm += *p;
+        }
+        for p in self.probs.iter_mut() {
+            *p /= norm;
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MutOpEntry {
+    prob: f64,
+    num_used: usize,
+    num_finds: usize,
+}
+
+impl serde::ser::Serialize for MutOpStats {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(MUT_OP_NUM))?;
+        for i in 0..MUT_OP_NUM {
+            let entry = MutOpEntry {
+                prob: self.probs[i],
+                num_used: self.counters[i].num_used,
+                num_finds: self.counters[i].num_finds,
+            };
+            map.serialize_entry(get_mut_op_name(i), &entry)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_probs() {
+        let sched = MutOpSched::default();
+        let sum: f64 = sched.probs.iter().sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+        assert!(sched.probs[MutOp::FlipBit.index()] > sched.probs[MutOp::RandomByte.index()]);
+    }
+
+    #[test]
+    fn test_sync_favors_finds() {
+        let mut global = MutOpStats::default();
+        let mut sched = MutOpSched::default();
+        let mut good = MutOpSet::default();
+        good.add(MutOp::Interesting);
+        let mut bad = MutOpSet::default();
+        bad.add(MutOp::RandomByte);
+        for _ in 0..100 {
+            sched.record(good, true);
+            sched.record(bad, false);
+        }
+        sched.sync(&mut global);
+        assert!(sched.probs[MutOp::Interesting.index()] > sched.probs[MutOp::RandomByte.index()]);
+        assert!(sched.probs[MutOp::RandomByte.index()] >= config::MUT_OP_MIN_PROB * 0.5);
+        assert_eq!(sched.counters[MutOp::Interesting.index()].num_used, 0);
+    }
+}
@@ -0,0 +1,143 @@
+// Custom mutators in shared libraries, loaded by dlopen.
+// The C ABI follows AFL++'s custom mutators:
+//   void *afl_custom_init(void *afl, unsigned int seed);
+//   size_t afl_custom_fuzz(void *data, uint8_t *buf, size_t buf_size, uint8_t **out_buf,
+//                          uint8_t *add_buf, size_t add_buf_size, size_t max_size);
+//   size_t afl_custom_post_process(void *data, uint8_t *buf, size_t buf_size, uint8_t **out_buf);
+//   void afl_custom_deinit(void *data);
+// `afl` is always NULL. `init` and `deinit` are required, the others are optional.
+// `*out_buf` is owned by the library and should stay valid until the next call;
+// returning 0 means no new input (fuzz) or keeping the input (post_process).
+
+use super::CustomMutator;
+use libc::{c_char, c_uint, c_void};
+use std::{ffi::CStr, ffi::CString, mem, ptr, slice};
+
+type InitFn = unsafe extern "C" fn(*mut c_void, c_uint) -> *mut c_void;
+type FuzzFn =
+    unsafe extern "C" fn(*mut c_void, *mut u8, usize, *mut *mut u8, *mut u8, usize, usize) -> usize;
+type PostProcessFn = unsafe extern "C" fn(*mut c_void, *mut u8, usize, *mut *mut u8) -> usize;
+type DeinitFn = unsafe extern "C" fn(*mut c_void);
+
+pub struct DlMutator {
+    handle: *mut c_void,
+    data: *mut c_void,
+    fuzz_fn: Option<FuzzFn>,
+    post_process_fn: Option<PostProcessFn>,
+    deinit_fn: DeinitFn,
+}
+
+fn dl_error() -> String {
+    unsafe {
+        let err = libc::dlerror();
+        if err.is_null() {
+            "unknown error".to_string()
+        } else {
+            CStr::from_ptr(err).to_string_lossy().into_owned()
+        }
+    }
+}
+
+unsafe fn dl_sym(handle: *mut c_void, name: &str) -> Option<*mut c_void> {
+    let name = CString::new(name).unwrap();
+    let sym = libc::dlsym(handle, name.as_ptr() as *const c_char);
+    if sym.is_null() {
+        None
+    } else {
+        Some(sym)
+    }
+}
+
+impl DlMutator {
+    pub fn new(path: &str, seed: u32) -> Result<Self, String> {
+        let c_path = CString::new(path).map_err(|e| e.to_string())?;
+        unsafe {
+            let handle = libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW);
+            if handle.is_null() {
+                return Err(dl_error());
+            }
+
+            let init_fn = match dl_sym(handle, "afl_custom_init") {
+                Some(f) => mem::transmute::<*mut c_void, InitFn>(f),
+                None => {
+                    libc::dlclose(handle);
+                    return Err("symbol afl_custom_init is not found".to_string());
+                },
+            };
+            let deinit_fn = match dl_sym(handle, "afl_custom_deinit") {
+                Some(f) => mem::transmute::<*mut c_void, DeinitFn>(f),
+                None => {
+                    libc::dlclose(handle);
+                    return Err("symbol afl_custom_deinit is not found".to_string());
+                },
+            };
+            let fuzz_fn = dl_sym(handle, "afl_custom_fuzz").map(|f| mem::transmute::<*mut c_void, FuzzFn>(f));
+            let post_process_fn = dl_sym(handle, "afl_custom_post_process")
+                .map(|f| mem::transmute::<*mut c_void, PostProcessFn>(f));
+
+            let data = init_fn(ptr::null_mut(), seed as c_uint);
+            if data.is_null() {
+                libc::dlclose(handle);
+                return Err("afl_custom_init returned NULL".to_string());
+            }
+
+            Ok(Self {
+                handle,
+                data,
+                fuzz_fn,
+                post_process_fn,
+                deinit_fn,
+            })
+        }
+    }
+}
+
+impl CustomMutator for DlMutator {
+    fn fuzz(&mut self, buf: &[u8], add_buf: &[u8], max_size: usize) -> Option<Vec<u8>> {
+        let fuzz_fn = self.fuzz_fn?;
+        // The library may mutate the buffers in place, so we pass copies.
+        let mut buf = buf.to_vec();
+        let mut add_buf = add_buf.to_vec();
+        let mut out_buf: *mut u8 = ptr::null_mut();
+        let size = unsafe {
+            fuzz_fn(
+                self.data,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut out_buf,
+                add_buf.as_mut_ptr(),
+                add_buf.len(),
+                max_size,
+            )
+        };
+        if size == 0 || out_buf.is_null() {
+            return None;
+        }
+        let size = std::cmp::min(size, max_size);
+        Some(unsafe { slice::from_raw_parts(out_buf, size) }.to_vec())
+    }
+
+    fn post_process(&mut self, buf: &[u8]) -> Option<Vec<u8>> {
+        let post_process_fn = self.post_process_fn?;
+        let mut buf = buf.to_vec();
+        let mut out_buf: *mut u8 = ptr::null_mut();
+        let size = unsafe { post_process_fn(self.data, buf.as_mut_ptr(), buf.len(), &mut out_buf) };
+        if size == 0 || out_buf.is_null() {
+            return None;
+        }
+        Some(unsafe { slice::from_raw_parts(out_buf, size) }.to_vec())
+    }
+
+    fn has_fuzz(&self) -> bool {
+        self.fuzz_fn.is_some()
+    }
+}
+
+impl Drop for DlMutator {
+    fn drop(&mut self) {
+        unsafe {
+            (self.deinit_fn)(self.data);
+            libc::dlclose(self.handle);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Custom mutators supplied by users, e.g. for structured input formats.
+// A mutator is created for each executor (init), and released when
+// the executor is dropped (deinit).
+mod dl;
+pub use self::dl::DlMutator;
+
+pub trait CustomMutator {
+    // Generate a new input from `buf`. `add_buf` is another input in the queue,
+    // which could be used for splicing.
+    // Return None if the mutator has nothing to offer for `buf`.
+    fn fuzz(&mut self, _buf: &[u8], _add_buf: &[u8], _max_size: usize) -> Option<Vec<u8>> {
+        None
+    }
+
+    // Fix the input before it is written to the target, e.g. magic values or checksums.
+    // Return None to keep the input as it is.
+    fn post_process(&mut self, _buf: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn has_fuzz(&self) -> bool {
+        false
+    }
+}
+
+pub fn load_custom_mutator(path: &str, seed: u32) -> Box<dyn CustomMutator> {
+    match DlMutator::new(path, seed) {
+        Ok(m) => Box::new(m),
+        Err(e) => {
+            error!("FATAL: Could not load custom mutator {}: {}", path, e);
+            panic!();
+        },
+    }
+}
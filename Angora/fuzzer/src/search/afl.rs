@@ -4,16 +4,26 @@
 // And GE algorithm.
 
 use super::*;
-use rand::{self, distributions::Uniform, Rng};
-use indexmap::IndexMap;
-use std::string::String;
+use super::focus::ByteFocus;
+use crate::mut_input::{LenEdit, LenField, MutOp, MutOpSched, MutOpSet};
+use rand::{self, distributions::WeightedIndex, Rng};
 
 static IDX_TO_SIZE: [usize; 4] = [1, 2, 4, 8];
+static HAVOC_BASE_OPS: [MutOp; 4] = [
+    MutOp::FlipBit,
+    MutOp::Arith,
+    MutOp::Interesting,
+    MutOp::RandomByte,
+];
+static HAVOC_LEN_OPS: [MutOp; 2] = [MutOp::DeleteBytes, MutOp::InsertBytes];
+static HAVOC_DICT_OPS: [MutOp; 2] = [MutOp::InsertDict, MutOp::OverwriteDict];
 
 pub struct AFLFuzz<'a> {
     handler: SearchHandler<'a>,
     run_ratio: usize,
     enable_dict: bool,
+    focus: ByteFocus,
+    len_fields: Vec<LenField>,
     //dictionary: Dict,
 }
 
@@ -30,15 +40,27 @@ impl<'a> AFLFuzz<'a> {
             5
         };
 
-        Self { handler, run_ratio, enable_dict }
+        Self {
+            handler,
+            run_ratio,
+            enable_dict,
+            focus: Default::default(),
+            len_fields: vec![],
+        }
     }
 
     pub fn run(&mut self) {
         if self.handler.cond.is_first_time() {
+            self.handler.executor.stage = "len";
             self.afl_len();
         }
 
-        self.handler.max_times = (config::MAX_SPLICE_TIMES * self.run_ratio).into();
+        let id = self.handler.cond.base.belong as usize;
+        self.len_fields = self.handler.executor.get_len_fields(id);
+
+        self.handler.executor.stage = "splice";
+        let max_splice_times = self.handler.executor.cmd.config.max_splice_times;
+        self.handler.max_times = (max_splice_times * self.run_ratio).into();
         loop {
             if self.handler.is_stopped_or_skip() {
                 break;
@@ -54,36 +76,71 @@ impl<'a> AFLFuzz<'a> {
             256
         };
 
-        
-        /*let max_choice = if config::ENABLE_MICRO_RANDOM_LEN {
-            8
-        } else {
-            6
-        };
-        */
-        let max_choice = if config::ENABLE_MICRO_RANDOM_LEN {
+
+        if self.handler.executor.cmd.enable_focus_havoc {
+            let (open, solved) = self.handler.executor.get_focus_offsets(id);
+            self.focus = ByteFocus::new(&open, &solved, self.handler.buf.len());
+        }
+
+        let mut ops = HAVOC_BASE_OPS.to_vec();
+        if self.handler.executor.cmd.config.enable_micro_random_len {
+            ops.extend_from_slice(&HAVOC_LEN_OPS);
             if self.enable_dict {
-                10
+                ops.extend_from_slice(&HAVOC_DICT_OPS);
             }
-            else {
-                8
-            }
-        } else {
-            6
-        };
-
-        let choice_range = Uniform::new(0, max_choice);
+        }
 
-        self.handler.max_times += (config::MAX_HAVOC_FLIP_TIMES * self.run_ratio).into();
+        self.handler.executor.stage = "havoc";
+        let max_havoc_flip_times = self.handler.executor.cmd.config.max_havoc_flip_times;
+        self.handler.max_times += (max_havoc_flip_times * self.run_ratio).into();
         self.handler.skip = false;
 
+        // The weights only change in MutOpSched::sync, when the stats are synced between the rounds.
+        let dist = self.handler.executor.local_stats.mut_ops.distribution(&ops);
         loop {
             if self.handler.is_stopped_or_skip() {
                 break;
             }
             let mut buf = self.handler.buf.clone();
-            self.havoc_flip(&mut buf, max_stacking, choice_range);
+            let mut edits = vec![];
+            let used = self.havoc_flip(&mut buf, max_stacking, &ops, &dist, &mut edits);
+            mut_input::fix_len_fields(&self.handler.buf, &mut buf, &self.len_fields, &edits);
             self.handler.execute(&buf);
+            self.handler.record_mut_ops(used);
+        }
+
+        if self.has_custom_fuzz() {
+            self.custom_fuzz();
+        }
+    }
+
+    fn has_custom_fuzz(&self) -> bool {
+        match &self.handler.executor.mutator {
+            Some(m) => m.has_fuzz(),
+            None => false,
+        }
+    }
+
+    fn custom_fuzz(&mut self) {
+        self.handler.executor.stage = "custom";
+        let max_custom_mutator_times = self.handler.executor.cmd.config.max_custom_mutator_times;
+        self.handler.max_times += (max_custom_mutator_times * self.run_ratio).into();
+        self.handler.skip = false;
+
+        loop {
+            if self.handler.is_stopped_or_skip() {
+                break;
+            }
+            let add_buf = self.handler.executor.random_input_buf();
+            let max_input_len = self.handler.executor.cmd.config.max_input_len;
+            let new_buf = match self.handler.executor.mutator.as_mut() {
+                Some(m) => m.fuzz(&self.handler.buf, &add_buf, max_input_len),
+                None => None,
+            };
+            match new_buf {
+                Some(buf) => self.handler.execute(&buf),
+                None => break,
+            }
         }
     }
 
@@ -103,7 +160,8 @@ impl<'a> AFLFuzz<'a> {
         (first_loc, last_loc)
     }
 
-    fn splice_two_vec(buf1: &Vec<u8>, buf2: &Vec<u8>) -> Option<Vec<u8>> {
+    // Return the new buffer and where it is split.
+    fn splice_two_vec(buf1: &Vec<u8>, buf2: &Vec<u8>) -> Option<(Vec<u8>, usize)> {
         let len = std::cmp::min(buf1.len(), buf2.len());
         if len < 2 {
             return None;
@@ -119,14 +177,25 @@ impl<'a> AFLFuzz<'a> {
         }
 
         let split_at = f_loc + rand::random::<usize>() % (l_loc - f_loc);
-        Some([&buf1[..split_at], &buf2[split_at..]].concat())
+        Some(([&buf1[..split_at], &buf2[split_at..]].concat(), split_at))
     }
 
     // GE algorithm
     fn splice(&mut self) -> bool {
         let buf1 = self.handler.buf.clone();
         let buf2 = self.handler.executor.random_input_buf();
-        if let Some(new_buf) = Self::splice_two_vec(&buf1, &buf2) {
+        if let Some((mut new_buf, split_at)) = Self::splice_two_vec(&buf1, &buf2) {
+            if buf1.len() != buf2.len() {
+                // Only the fields in the head from buf1 are kept.
+                let fields: Vec<LenField> = self
+                    .len_fields
+                    .iter()
+                    .filter(|f| ((f.begin + f.width) as usize) <= split_at)
+                    .cloned()
+                    .collect();
+                let edit = (split_at as u32, buf2.len() as i64 - buf1.len() as i64);
+                mut_input::fix_len_fields(&buf1, &mut new_buf, &fields, &[edit]);
+            }
             self.handler.execute(&new_buf);
             true
         } else {
@@ -135,26 +204,37 @@ impl<'a> AFLFuzz<'a> {
     }
 
     // TODO both endian?
-    fn havoc_flip(&self, buf: &mut Vec<u8>, max_stacking: usize, choice_range: Uniform<u32>) {
+    fn havoc_flip(
+        &self,
+        buf: &mut Vec<u8>,
+        max_stacking: usize,
+        ops: &[MutOp],
+        dist: &WeightedIndex<f64>,
+        edits: &mut Vec<LenEdit>,
+    ) -> MutOpSet {
         let mut rng = rand::thread_rng();
+        let config = &self.handler.executor.cmd.config;
         let mut byte_len = buf.len() as u32;
         let use_stacking = 1 + rng.gen_range(0, max_stacking);
+        let mut used = MutOpSet::default();
 
         for _ in 0..use_stacking {
-            match rng.sample(choice_range) {
-                0 | 1 => {
+            let op = MutOpSched::pick(ops, dist, &mut rng);
+            used.add(op);
+            match op {
+                MutOp::FlipBit => {
                     // flip bit
-                    let byte_idx: u32 = rng.gen_range(0, byte_len);
+                    let byte_idx: u32 = self.focus.pick(&mut rng, byte_len);
                     let bit_idx: u32 = rng.gen_range(0, 8);
                     buf[byte_idx as usize] ^= 128 >> bit_idx;
                 },
-                2 | 3 => {
+                MutOp::Arith => {
                     //add or sub
                     let n: u32 = rng.gen_range(0, 3);
                     let size = IDX_TO_SIZE[n as usize];
                     if byte_len > size as u32 {
-                        let byte_idx: u32 = rng.gen_range(0, byte_len - size as u32);
-                        let v: u32 = rng.gen_range(0, config::MUTATE_ARITH_MAX);
+                        let byte_idx: u32 = self.focus.pick(&mut rng, byte_len - size as u32);
+                        let v: u32 = rng.gen_range(0, config.mutate_arith_max);
                         let direction: bool = rng.gen();
                         mut_input::update_val_in_buf(
                             buf,
@@ -166,24 +246,24 @@ impl<'a> AFLFuzz<'a> {
                         );
                     }
                 },
-                4 => {
+                MutOp::Interesting => {
                     // set interesting value
                     let n: u32 = rng.gen_range(0, 3);
                     let size = IDX_TO_SIZE[n as usize];
                     if byte_len > size as u32 {
-                        let byte_idx: u32 = rng.gen_range(0, byte_len - size as u32);
+                        let byte_idx: u32 = self.focus.pick(&mut rng, byte_len - size as u32);
                         let vals = get_interesting_bytes(size);
                         let wh = rng.gen_range(0, vals.len() as u32);
                         mut_input::set_val_in_buf(buf, byte_idx as usize, size, vals[wh as usize]);
                     }
                 },
-                5 => {
+                MutOp::RandomByte => {
                     // random byte
-                    let byte_idx: u32 = rng.gen_range(0, byte_len);
+                    let byte_idx: u32 = self.focus.pick(&mut rng, byte_len);
                     let val: u8 = rng.gen();
                     buf[byte_idx as usize] = val;
                 },
-                6 => {
+                MutOp::DeleteBytes => {
                     // delete bytes
                     let remove_len: u32 = rng.gen_range(1, 5);
                     if byte_len > remove_len {
@@ -193,21 +273,23 @@ impl<'a> AFLFuzz<'a> {
                         for _ in 0..remove_len {
                             buf.remove(byte_idx as usize);
                         }
+                        edits.push((byte_idx, -(remove_len as i64)));
                     }
                 },
-                7 => {
+                MutOp::InsertBytes => {
                     // insert bytes
                     let add_len = rng.gen_range(1, 5);
                     let new_len = byte_len + add_len;
-                    if new_len < config::MAX_INPUT_LEN as u32 {
+                    if new_len < config.max_input_len as u32 {
                         let byte_idx: u32 = rng.gen_range(0, byte_len);
                         byte_len = new_len;
                         for i in 0..add_len {
                             buf.insert((byte_idx + i) as usize, rng.gen());
                         }
+                        edits.push((byte_idx, add_len as i64));
                     }
                 },
-                8 => {
+                MutOp::InsertDict => {
                     // insert dict
                     let d = match self.handler.executor.dictionary.read() {
                         Ok(guard) => guard,
@@ -225,15 +307,16 @@ impl<'a> AFLFuzz<'a> {
                     let extra_len = word.len() as u32;
                     let new_len = byte_len + extra_len;
 
-                    if new_len < config::MAX_INPUT_LEN as u32 {
+                    if new_len < config.max_input_len as u32 {
                         let insert_at: u32 = rng.gen_range(0, byte_len + 1);
                         byte_len = new_len;
                         for i in 0..extra_len {
                             buf.insert((insert_at + i) as usize, word[i as usize]);
                         }
+                        edits.push((insert_at, extra_len as i64));
                     }
                 },
-                9 => {
+                MutOp::OverwriteDict => {
                     // overwrite bytes with dict
                     let d = match self.handler.executor.dictionary.read() {
                         Ok(guard) => guard,
@@ -251,18 +334,19 @@ impl<'a> AFLFuzz<'a> {
                     let extra_len = word.len() as u32;
 
                     if byte_len > extra_len {
-                        let insert_at: u32 = rng.gen_range(0, byte_len - extra_len as u32 + 1);
+                        let insert_at: u32 = self.focus.pick(&mut rng, byte_len - extra_len as u32 + 1);
                         mut_input::set_word_in_buf(buf, insert_at as usize, extra_len as usize, word);
                     }
                 },
-                _ => {},
             }
         }
+        used
     }
 
     fn random_len(&mut self) {
+        let max_input_len = self.handler.executor.cmd.config.max_input_len;
         let len = self.handler.buf.len();
-        if len > config::MAX_INPUT_LEN {
+        if len > max_input_len {
             return;
         }
 
@@ -271,12 +355,12 @@ impl<'a> AFLFuzz<'a> {
         let mut rng = rand::thread_rng();
 
         let mut buf = self.handler.buf.clone();
-        for _ in 0..config::RANDOM_LEN_NUM {
+        for _ in 0..self.handler.executor.cmd.config.random_len_num {
             let step = rng.gen::<usize>() % orig_len + 1;
             let mut v = vec![0u8; step];
             rng.fill_bytes(&mut v);
             buf.append(&mut v);
-            if buf.len() < config::MAX_INPUT_LEN {
+            if buf.len() < max_input_len {
                 self.handler.execute(&buf);
             } else {
                 break;
@@ -285,8 +369,9 @@ impl<'a> AFLFuzz<'a> {
     }
 
     fn add_small_len(&mut self) {
+        let max_input_len = self.handler.executor.cmd.config.max_input_len;
         let len = self.handler.buf.len();
-        if len > config::MAX_INPUT_LEN {
+        if len > max_input_len {
             return;
         }
 
@@ -298,7 +383,7 @@ impl<'a> AFLFuzz<'a> {
             rng.fill_bytes(&mut v);
             buf.append(&mut v);
             step = step * 2;
-            if buf.len() < config::MAX_INPUT_LEN {
+            if buf.len() < max_input_len {
                 self.handler.execute(&buf);
             } else {
                 break;
@@ -307,7 +392,7 @@ impl<'a> AFLFuzz<'a> {
     }
 
     fn afl_len(&mut self) {
-        if config::ENABLE_RANDOM_LEN {
+        if self.handler.executor.cmd.config.enable_random_len {
             self.random_len();
         } else {
             self.add_small_len();
@@ -344,7 +429,7 @@ mod tests {
         let buf1: Vec<u8> = vec![1, 2, 3, 4, 5];
         let buf2: Vec<u8> = vec![1, 2, 2, 2, 5, 6];
 
-        let new_vec = AFLFuzz::splice_two_vec(&buf1, &buf2).unwrap();
+        let (new_vec, _) = AFLFuzz::splice_two_vec(&buf1, &buf2).unwrap();
         println!("{:?}", new_vec);
         // split at index 2 or 3
         assert!(new_vec == vec![1, 2, 2, 4, 5, 6] || new_vec == vec![1, 2, 2, 2, 5, 6]);
@@ -35,8 +35,9 @@ impl<'a> CbhSearch<'a> {
                 break;
             }
             input.assign(&input_min);
-            input.randomize_all(self.enable_dict, self.handler.executor.dictionary.clone());
+            let used = input.randomize_all(self.enable_dict, self.handler.executor.dictionary.clone(), &self.handler.executor.local_stats.mut_ops);
             let (f0, ret) = self.handler.execute_cond(&input);
+            self.handler.record_mut_ops(used);
 
             if self.enable_dict && ret.len() > 0 {
                 let mut d = match self.handler.executor.dictionary.write() {
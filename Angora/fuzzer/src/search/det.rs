@@ -12,7 +12,7 @@ impl<'a> DetFuzz<'a> {
     pub fn bitflip1(&mut self) {
         debug!("detministic steps");
         let mut input = self.handler.get_f_input();
-        let n = cmp::min(input.val_len() << 3, config::MAX_SEARCH_EXEC_NUM);
+        let n = cmp::min(input.val_len() << 3, self.handler.executor.cmd.config.max_search_exec_num);
         for i in 0..n {
             if self.handler.cond.is_done() {
                 break;
@@ -1,5 +1,6 @@
 use super::*;
 
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use std::string::String;
 
 pub struct ExploitFuzz<'a> {
@@ -17,6 +18,7 @@ impl<'a> ExploitFuzz<'a> {
         let orig_input_val = input.get_value();
 
         if self.handler.cond.is_first_time() {
+            self.exploit_bound(&mut input, &orig_input_val);
             // use interesting values
             for i in 0..input.len() {
                 let n = input.get_entry_len(i);
@@ -33,14 +35,16 @@ impl<'a> ExploitFuzz<'a> {
             }
         }
 
-        self.handler.max_times = config::MAX_EXPLOIT_EXEC_NUM.into();
+        input.assign(&orig_input_val);
+        self.handler.max_times = self.handler.executor.cmd.config.max_exploit_exec_num.into();
         loop {
             if self.handler.is_stopped_or_skip() {
                 break;
             }
             input.assign(&orig_input_val);
-            input.randomize_all(self.enable_dict, self.handler.executor.dictionary.clone());
+            let used = input.randomize_all(self.enable_dict, self.handler.executor.dictionary.clone(), &self.handler.executor.local_stats.mut_ops);
             let ret = self.handler.execute_input(&input);
+            self.handler.record_mut_ops(used);
             
             if self.enable_dict && ret.len() > 0 {
                 let mut d = match self.handler.executor.dictionary.write() {
@@ -54,4 +58,57 @@ impl<'a> ExploitFuzz<'a> {
             }
         }
     }
+
+    // The tracker records the bound of the allocation indexed by the value in arg2.
+    // Aim at the values around the bound, assuming the value is directly copied from input.
+    fn exploit_bound(&mut self, input: &mut MutInput, orig_input_val: &[u8]) {
+        let bound = self.handler.cond.base.arg2;
+        let size = orig_input_val.len();
+        if bound == 0 || size == 0 || size > 8 {
+            return;
+        }
+        let val = self.handler.cond.base.arg1;
+        let orig = LittleEndian::read_uint(orig_input_val, size);
+        let big_endian = orig != val && BigEndian::read_uint(orig_input_val, size) == val;
+        let orig = if big_endian {
+            BigEndian::read_uint(orig_input_val, size)
+        } else {
+            orig
+        };
+
+        let mut buf = vec![0u8; size];
+        for &target in get_bound_vals(bound).iter() {
+            if self.handler.is_stopped_or_skip() {
+                break;
+            }
+            // linear
+            let v = orig.wrapping_add(target.wrapping_sub(val));
+            if big_endian {
+                BigEndian::write_uint(&mut buf, truncate(v, size), size);
+            } else {
+                LittleEndian::write_uint(&mut buf, truncate(v, size), size);
+            }
+            input.assign(&buf);
+            self.handler.execute_input(input);
+        }
+    }
+}
+
+fn get_bound_vals(bound: u64) -> Vec<u64> {
+    vec![
+        bound - 1,
+        bound,
+        bound + 1,
+        bound + 2,
+        bound.wrapping_mul(2),
+        bound.wrapping_neg(),
+    ]
+}
+
+fn truncate(v: u64, size: usize) -> u64 {
+    if size < 8 {
+        v & ((1 << (size * 8)) - 1)
+    } else {
+        v
+    }
 }
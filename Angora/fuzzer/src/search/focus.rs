@@ -0,0 +1,76 @@
+// Byte positions for the taint-restricted havoc.
+// Positions referenced by the pending conditions of the input are preferred,
+// and the ones only referenced by solved conditions are avoided.
+use angora_common::{config, tag::TagSeg};
+use rand::Rng;
+
+#[derive(Debug, Default)]
+pub struct ByteFocus {
+    open: Vec<u32>,
+    avoid: Vec<bool>,
+}
+
+impl ByteFocus {
+    pub fn new(open: &[TagSeg], solved: &[TagSeg], len: usize) -> Self {
+        let mut is_open = vec![false; len];
+        let mut avoid = vec![false; len];
+        for seg in open {
+            let end = std::cmp::min(seg.end as usize, len);
+            for v in is_open.iter_mut().take(end).skip(seg.begin as usize) {
+                *v = true;
+            }
+        }
+        for seg in solved {
+            for i in (seg.begin as usize)..std::cmp::min(seg.end as usize, len) {
+                avoid[i] = !is_open[i];
+            }
+        }
+        let open = (0..len as u32).filter(|i| is_open[*i as usize]).collect();
+        Self { open, avoid }
+    }
+
+    // Pick a position in [0, bound).
+    pub fn pick<R: Rng>(&self, rng: &mut R, bound: u32) -> u32 {
+        if !self.open.is_empty() && rng.gen_bool(config::FOCUS_HAVOC_OPEN_PROB) {
+            // `open` is sorted, and positions beyond the bound are out.
+            let n = self.open.iter().take_while(|i| **i < bound).count();
+            if n > 0 {
+                return self.open[rng.gen_range(0, n)];
+            }
+        }
+        let mut idx = rng.gen_range(0, bound);
+        for _ in 0..config::FOCUS_HAVOC_MAX_RETRY {
+            if !self.avoid.get(idx as usize).cloned().unwrap_or(false) {
+                break;
+            }
+            idx = rng.gen_range(0, bound);
+        }
+        idx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(begin: u32, end: u32) -> TagSeg {
+        TagSeg {
+            sign: false,
+            begin,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_focus_pick() {
+        let mut rng = rand::thread_rng();
+        // [2, 4) is open, [0, 4) is solved, [8, 20) is out of the buffer.
+        let focus = ByteFocus::new(&[seg(2, 4), seg(8, 20)], &[seg(0, 4)], 10);
+        assert_eq!(focus.open, vec![2, 3, 8, 9]);
+        assert_eq!(focus.avoid[..4], [true, true, false, false]);
+        for _ in 0..100 {
+            let idx = focus.pick(&mut rng, 3);
+            assert!(idx < 3);
+        }
+    }
+}
@@ -39,8 +39,9 @@ impl<'a> GdSearch<'a> {
                 break;
             }
             input.assign(&input_min);
-            input.randomize_all_with_weight(rng, 3, self.enable_dict, self.handler.executor.dictionary.clone());
+            let used = input.randomize_all_with_weight(rng, 3, self.enable_dict, self.handler.executor.dictionary.clone(), &self.handler.executor.local_stats.mut_ops);
             let f0 = self.execute(&input).0;
+            self.handler.record_mut_ops(used);
             if f0 < fmin {
                 fmin = f0;
                 input_min = input.get_value();
@@ -84,7 +85,7 @@ impl<'a> GdSearch<'a> {
 
             let mut g_i = 0;
             while grad.max_val() == 0 {
-                if self.handler.is_stopped_or_skip() || g_i > config::MAX_NUM_MINIMAL_OPTIMA_ROUND {
+                if self.handler.is_stopped_or_skip() || g_i > self.handler.executor.cmd.config.max_num_minimal_optima_round {
                     break;
                 }
                 debug!("Stuck in minor optima! g_i={} f0={}", g_i, f0);
@@ -99,7 +100,7 @@ impl<'a> GdSearch<'a> {
                 self.cal_gradient(&input, f0, &mut grad);
             }
 
-            if self.handler.is_stopped_or_skip() || g_i > config::MAX_NUM_MINIMAL_OPTIMA_ROUND {
+            if self.handler.is_stopped_or_skip() || g_i > self.handler.executor.cmd.config.max_num_minimal_optima_round {
                 break;
             }
 
@@ -330,7 +331,7 @@ impl<'a> GdSearch<'a> {
                 // until the gradient is not work
                 // or f_new > f_last
                 if f_new >= f_last {
-                    if f_new == std::u64::MAX || rng.gen_bool(config::GD_ESCAPE_RATIO) {
+                    if f_new == std::u64::MAX || rng.gen_bool(self.handler.executor.cmd.config.gd_escape_ratio) {
                         break;
                     }
                 }
@@ -21,12 +21,13 @@ impl<'a> SearchHandler<'a> {
     ) -> Self {
         executor.local_stats.register(cond);
         cond.fuzz_times = cond.fuzz_times + 1;
+        let max_times = executor.cmd.config.max_search_exec_num.into();
         Self {
             running,
             executor,
             cond,
             buf,
-            max_times: config::MAX_SEARCH_EXEC_NUM.into(),
+            max_times,
             skip: false,
         }
     }
@@ -45,7 +46,7 @@ impl<'a> SearchHandler<'a> {
 
         // bonus
         if self.executor.has_new_path {
-            self.max_times += config::BONUS_EXEC_NUM.into();
+            self.max_times += self.executor.cmd.config.bonus_exec_num.into();
         }
 
         // Skip if it reach max epoch,
@@ -87,6 +88,12 @@ impl<'a> SearchHandler<'a> {
         self.process_status(status);
     }
 
+    // Credit the havoc operators used in the last execution.
+    pub fn record_mut_ops(&mut self, used: mut_input::MutOpSet) {
+        let has_new_path = self.executor.has_new_path;
+        self.executor.local_stats.mut_ops.record(used, has_new_path);
+    }
+
     pub fn get_f_input(&self) -> MutInput {
         debug!("input offset: {:?}", self.cond.offsets);
         MutInput::from(&self.cond.offsets, &self.buf)
@@ -95,6 +102,7 @@ impl<'a> SearchHandler<'a> {
 
 impl<'a> Drop for SearchHandler<'a> {
     fn drop(&mut self) {
+        self.cond.num_exec += self.executor.local_stats.num_exec.0;
         self.executor.update_log();
     }
 }
@@ -12,7 +12,7 @@ impl<'a> LenFuzz<'a> {
     }
 
     pub fn run(&mut self) {
-        if !config::ENABLE_INPUT_LEN_EXPLORATION {
+        if !self.handler.executor.cmd.config.enable_input_len_exploration {
             self.handler.cond.mark_as_done();
             return;
         }
@@ -25,6 +25,7 @@ impl<'a> LenFuzz<'a> {
         //let offset = self.handler.cond.base.lb1 as usize;
         let size = self.handler.cond.base.lb2 as usize;
         let delta = self.handler.cond.base.get_output() as usize;
+        let max_input_len = self.handler.executor.cmd.config.max_input_len;
         let mut buf = self.handler.buf.clone();
         debug!(
             "len: delta {}, size: {}, buf_len: {}",
@@ -34,9 +35,9 @@ impl<'a> LenFuzz<'a> {
         );
         if delta > 0 {
             let extended_len = delta * size;
-            if extended_len < config::MAX_INPUT_LEN {
+            if extended_len < max_input_len {
                 let buf_len = buf.len();
-                if buf_len + extended_len < config::MAX_INPUT_LEN {
+                if buf_len + extended_len < max_input_len {
                     // len > X
                     let mut v = vec![0u8; extended_len + 1];
                     rand::thread_rng().fill_bytes(&mut v);
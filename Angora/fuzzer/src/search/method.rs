@@ -6,6 +6,17 @@ pub enum SearchMethod {
     Mb,
 }
 
+impl SearchMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchMethod::Gd => "gd",
+            SearchMethod::Random => "random",
+            SearchMethod::Cbh => "cbh",
+            SearchMethod::Mb => "mb",
+        }
+    }
+}
+
 pub fn parse_search_method(m: &str) -> SearchMethod {
     match m {
         "gd" => SearchMethod::Gd,
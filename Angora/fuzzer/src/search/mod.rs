@@ -3,7 +3,6 @@ use crate::{
     executor::{Executor, StatusType},
     mut_input::{self, MutInput},
 };
-use angora_common::config;
 use rand::prelude::*;
 use std::{
     self,
@@ -38,6 +37,7 @@ pub use self::cmpfn::FnFuzz;
 pub mod len;
 pub use self::len::LenFuzz;
 pub mod afl;
+mod focus;
 pub use self::afl::AFLFuzz;
 pub mod exploit;
 pub use self::exploit::ExploitFuzz;
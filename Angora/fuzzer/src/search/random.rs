@@ -43,8 +43,9 @@ impl<'a> RandomSearch<'a> {
                 break;
             }
             input.assign(&orig_input_val);
-            input.randomize_all(self.enable_dict, self.handler.executor.dictionary.clone());
+            let used = input.randomize_all(self.enable_dict, self.handler.executor.dictionary.clone(), &self.handler.executor.local_stats.mut_ops);
             let ret = self.handler.execute_cond(&input).1;
+            self.handler.record_mut_ops(used);
             if self.enable_dict && ret.len() > 0 {
                 let mut d = match self.handler.executor.dictionary.write() {
                     Ok(guard) => guard,
@@ -1,9 +1,23 @@
 use super::*;
-use crate::{branches::GlobalBranches, depot::Depot};
+use crate::{branches::GlobalBranches, depot::Depot, mut_input::MutOpStats, stop::Progress};
 use colored::*;
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 use std::sync::Arc;
 
+// The counters that should go on after resuming.
+#[derive(Serialize, Deserialize)]
+pub struct StatsCheckpoint {
+    run_time: time::Duration,
+    track_time: time::Duration,
+    num_rounds: usize,
+    num_exec: usize,
+    num_inputs: usize,
+    num_hangs: usize,
+    num_crashes: usize,
+    #[serde(default)]
+    crash_kinds: Vec<usize>,
+}
+
 #[derive(Default, Serialize)]
 pub struct ChartStats {
     init_time: TimeIns,
@@ -21,10 +35,17 @@ pub struct ChartStats {
     num_inputs: Counter,
     num_hangs: Counter,
     num_crashes: Counter,
+    num_unique_crashes: Counter,
+    crash_kinds: CrashStats,
+
+    cache_hits: Counter,
+    cache_misses: Counter,
+    cache_size: Counter,
 
     fuzz: FuzzStats,
     search: SearchStats,
     state: StateStats,
+    mut_ops: MutOpStats,
 }
 
 impl ChartStats {
@@ -38,6 +59,7 @@ impl ChartStats {
 
         local.avg_edge_num.sync(&mut self.avg_edge_num);
         local.avg_exec_time.sync(&mut self.avg_exec_time);
+        local.mut_ops.sync(&mut self.mut_ops);
 
         let st = self.fuzz.get_mut(local.fuzz_type.index());
         st.time += local.start_time.into();
@@ -52,6 +74,7 @@ impl ChartStats {
         self.num_hangs += local.num_hangs;
         st.num_crashes += local.num_crashes;
         self.num_crashes += local.num_crashes;
+        self.crash_kinds.add(&local.crash_kinds);
 
         //local.clear();
     }
@@ -59,6 +82,7 @@ impl ChartStats {
     pub fn sync_from_global(&mut self, depot: &Arc<Depot>, gb: &Arc<GlobalBranches>) {
         self.get_speed();
         self.iter_pq(depot);
+        self.sync_from_cache(depot);
         self.sync_from_branches(gb);
     }
 
@@ -87,6 +111,13 @@ impl ChartStats {
         self.max_rounds = max_round.into();
     }
 
+    fn sync_from_cache(&mut self, depot: &Arc<Depot>) {
+        let (hits, misses, size) = depot.cache_stats();
+        self.cache_hits = hits.into();
+        self.cache_misses = misses.into();
+        self.cache_size = (size >> 10).into();
+    }
+
     fn sync_from_branches(&mut self, gb: &Arc<GlobalBranches>) {
         self.density = Average::new(gb.get_density(), 0);
     }
@@ -133,6 +164,49 @@ impl ChartStats {
         )
     }
 
+    pub fn checkpoint(&self) -> StatsCheckpoint {
+        StatsCheckpoint {
+            run_time: self.init_time.0.elapsed(),
+            track_time: self.track_time.0,
+            num_rounds: self.num_rounds.0,
+            num_exec: self.num_exec.0,
+            num_inputs: self.num_inputs.0,
+            num_hangs: self.num_hangs.0,
+            num_crashes: self.num_crashes.0,
+            crash_kinds: self.crash_kinds.counts(),
+        }
+    }
+
+    pub fn restore(&mut self, c: &StatsCheckpoint) {
+        let now = time::Instant::now();
+        self.init_time = TimeIns(now.checked_sub(c.run_time).unwrap_or(now));
+        self.track_time = c.track_time.into();
+        self.num_rounds = c.num_rounds.into();
+        self.num_exec = c.num_exec.into();
+        self.num_inputs = c.num_inputs.into();
+        self.num_hangs = c.num_hangs.into();
+        self.num_crashes = c.num_crashes.into();
+        self.crash_kinds = CrashStats::from_slice(&c.crash_kinds);
+    }
+
+    pub fn get_num_exec(&self) -> usize {
+        self.num_exec.0
+    }
+
+    pub fn progress(&self, num_edges: usize) -> Progress {
+        Progress {
+            run_time: self.init_time.0.elapsed().as_secs(),
+            num_exec: self.num_exec.0,
+            num_inputs: self.num_inputs.0,
+            num_crashes: self.num_crashes.0,
+            num_edges,
+        }
+    }
+
+    pub fn set_unique_crashes(&mut self, n: usize) {
+        self.num_unique_crashes = n.into();
+    }
+
     pub fn get_explore_num(&self) -> usize {
         self.fuzz
             .get(fuzz_type::FuzzType::ExploreFuzz.index())
@@ -165,7 +239,9 @@ impl fmt::Display for ChartStats {
   COVERAGE |    EDGE: {},   DENSITY: {}%
     EXECS  |   TOTAL: {},     ROUND: {},     MAX_R: {}
     SPEED  |  PERIOD: {:6}r/s    TIME: {}us, 
-    FOUND  |    PATH: {},     HANGS: {},   CRASHES: {}
+    FOUND  |    PATH: {},     HANGS: {},   CRASHES: {},    UNIQUE: {}
+  CRASHES  |  {}
+    CACHE  |    HITS: {},    MISSES: {},      SIZE: {}KB
 {}
 {}
 {}
@@ -188,6 +264,11 @@ impl fmt::Display for ChartStats {
             self.num_inputs,
             self.num_hangs,
             self.num_crashes,
+            self.num_unique_crashes,
+            self.crash_kinds,
+            self.cache_hits,
+            self.cache_misses,
+            self.cache_size,
             " -- FUZZ -- ".blue().bold(),
             self.fuzz,
             " -- SEARCH -- ".blue().bold(),
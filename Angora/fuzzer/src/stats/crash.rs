@@ -0,0 +1,45 @@
+use super::*;
+use crate::executor::{CrashKind, CRASH_KIND_NUM};
+use serde_derive::Serialize;
+
+// The crashes by what finds them, a signal or a sanitizer.
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct CrashStats([Counter; CRASH_KIND_NUM]);
+
+impl CrashStats {
+    pub fn count(&mut self, kind: CrashKind) {
+        self.0[kind.index()].count();
+    }
+
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        for (c, o) in self.0.iter_mut().zip(other.0.iter()) {
+            *c += *o;
+        }
+    }
+
+    pub fn counts(&self) -> Vec<usize> {
+        self.0.iter().map(|c| c.0).collect()
+    }
+
+    pub fn from_slice(counts: &[usize]) -> Self {
+        let mut s = Self::default();
+        for (c, n) in s.0.iter_mut().zip(counts.iter()) {
+            *c = (*n).into();
+        }
+        s
+    }
+}
+
+impl fmt::Display for CrashStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kinds: Vec<String> = CrashKind::ALL
+            .iter()
+            .map(|k| format!("{}: {}", k.name().to_uppercase(), self.0[k.index()]))
+            .collect();
+        write!(f, "{}", kinds.join(", "))
+    }
+}
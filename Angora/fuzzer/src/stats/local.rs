@@ -1,5 +1,5 @@
 use super::*;
-use crate::{cond_stmt::CondStmt, executor::StatusType, fuzz_type::FuzzType};
+use crate::{cond_stmt::CondStmt, executor::StatusType, fuzz_type::FuzzType, mut_input::MutOpSched};
 
 #[derive(Default)]
 pub struct LocalStats {
@@ -9,12 +9,15 @@ pub struct LocalStats {
     pub num_inputs: Counter,
     pub num_hangs: Counter,
     pub num_crashes: Counter,
+    pub crash_kinds: CrashStats,
 
     pub track_time: TimeDuration,
     pub start_time: TimeIns,
 
     pub avg_exec_time: SyncAverage,
     pub avg_edge_num: SyncAverage,
+
+    pub mut_ops: MutOpSched,
 }
 
 impl LocalStats {
@@ -28,6 +31,7 @@ impl LocalStats {
         self.num_inputs = Default::default();
         self.num_hangs = Default::default();
         self.num_crashes = Default::default();
+        self.crash_kinds.clear();
 
         self.start_time = Default::default();
         self.track_time = Default::default();
@@ -41,8 +45,9 @@ impl LocalStats {
             StatusType::Timeout => {
                 self.num_hangs.count();
             }
-            StatusType::Crash => {
+            StatusType::Crash(kind) => {
                 self.num_crashes.count();
+                self.crash_kinds.count(*kind);
             }
             _ => {}
         }
@@ -3,6 +3,7 @@ use std::{fmt, time};
 
 mod bunny;
 mod chart;
+mod crash;
 mod entry;
 mod format;
 mod fuzz;
@@ -11,7 +12,7 @@ mod search;
 mod show;
 mod state;
 
-pub use self::{bunny::*, chart::*, entry::*, local::*};
+pub use self::{bunny::*, chart::*, crash::*, entry::*, local::*};
 use self::{fuzz::*, search::*, state::*};
 
 pub use self::{format::*, show::*};
@@ -0,0 +1,159 @@
+// Stop criteria for bounded campaigns, e.g. in CI,
+// and the summary and exit code when the fuzzer ends.
+use angora_common::defs;
+use serde_derive::Serialize;
+use std::{fs, path::Path};
+
+#[derive(Debug, Default, Clone)]
+pub struct StopCriteria {
+    // seconds of wall-clock time
+    pub max_time: Option<u64>,
+    pub max_execs: Option<usize>,
+    pub on_crash: bool,
+    // covered edges
+    pub edges: Option<usize>,
+    // minutes without new paths
+    pub no_new_path: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    Interrupted,
+    MaxTime,
+    MaxExecs,
+    Crash,
+    Edges,
+    NoNewPath,
+    AllSolved,
+    NoConstraint,
+}
+
+impl StopReason {
+    pub fn is_error(&self) -> bool {
+        *self == StopReason::NoConstraint
+    }
+}
+
+// What the criteria are checked against.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Progress {
+    pub run_time: u64,
+    pub num_exec: usize,
+    pub num_inputs: usize,
+    pub num_crashes: usize,
+    pub num_edges: usize,
+}
+
+pub struct StopWatch {
+    criteria: StopCriteria,
+    last_inputs: usize,
+    // run time when the last new path was found
+    last_new_path: u64,
+}
+
+impl StopWatch {
+    pub fn new(criteria: StopCriteria) -> Self {
+        Self {
+            criteria,
+            last_inputs: 0,
+            last_new_path: 0,
+        }
+    }
+
+    pub fn check(&mut self, p: &Progress) -> Option<StopReason> {
+        if p.num_inputs != self.last_inputs {
+            self.last_inputs = p.num_inputs;
+            self.last_new_path = p.run_time;
+        }
+        let c = &self.criteria;
+        if c.on_crash && p.num_crashes > 0 {
+            Some(StopReason::Crash)
+        } else if c.edges.is_some_and(|n| p.num_edges >= n) {
+            Some(StopReason::Edges)
+        } else if c.max_execs.is_some_and(|n| p.num_exec >= n) {
+            Some(StopReason::MaxExecs)
+        } else if c.max_time.is_some_and(|t| p.run_time >= t) {
+            Some(StopReason::MaxTime)
+        } else if c
+            .no_new_path
+            .is_some_and(|m| p.run_time >= self.last_new_path + m * 60)
+        {
+            Some(StopReason::NoNewPath)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn exit_code(reason: StopReason, has_error: bool, num_crashes: usize) -> i32 {
+    if has_error || reason.is_error() {
+        defs::EXIT_ERROR
+    } else if num_crashes > 0 {
+        defs::EXIT_CRASH
+    } else {
+        defs::EXIT_CLEAN
+    }
+}
+
+pub fn write_summary(
+    out_dir: &Path,
+    reason: StopReason,
+    code: i32,
+    progress: &Progress,
+    stats: serde_json::Value,
+) {
+    let summary = serde_json::json!({
+        "stop_reason": reason,
+        "exit_code": code,
+        "progress": progress,
+        "stats": stats,
+    });
+    if let Err(e) = fs::write(out_dir.join(defs::SUMMARY_FILE), summary.to_string()) {
+        warn!("Could not write summary file: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stop_criteria() {
+        let mut sw = StopWatch::new(StopCriteria {
+            max_time: Some(3600),
+            no_new_path: Some(10),
+            ..Default::default()
+        });
+        let mut p = Progress {
+            run_time: 60,
+            num_inputs: 5,
+            ..Default::default()
+        };
+        assert_eq!(sw.check(&p), None);
+        p.run_time = 600;
+        assert_eq!(sw.check(&p), None);
+        p.run_time = 660;
+        assert_eq!(sw.check(&p), Some(StopReason::NoNewPath));
+        // A new path resets it.
+        p.num_inputs = 6;
+        assert_eq!(sw.check(&p), None);
+        p.run_time = 3600;
+        assert_eq!(sw.check(&p), Some(StopReason::MaxTime));
+
+        let mut sw = StopWatch::new(StopCriteria {
+            on_crash: true,
+            edges: Some(100),
+            ..Default::default()
+        });
+        p.num_edges = 100;
+        assert_eq!(sw.check(&p), Some(StopReason::Edges));
+        p.num_crashes = 1;
+        assert_eq!(sw.check(&p), Some(StopReason::Crash));
+
+        assert_eq!(exit_code(StopReason::Crash, false, 1), defs::EXIT_CRASH);
+        assert_eq!(exit_code(StopReason::MaxTime, false, 0), defs::EXIT_CLEAN);
+        assert_eq!(exit_code(StopReason::MaxTime, true, 1), defs::EXIT_ERROR);
+        assert_eq!(exit_code(StopReason::NoConstraint, false, 0), defs::EXIT_ERROR);
+    }
+}
@@ -0,0 +1,179 @@
+// Test case minimization, like afl-tmin:
+// shrink an input while the program behaves the same on it.
+// The same status is required for crashes and hangs,
+// and the same trace for normal inputs or if `exact_trace` is set.
+use crate::executor::{StatusType, TraceRunner};
+use angora_common::config;
+use std::{collections::HashSet, fs, path::Path};
+
+static TMP_DIR: &str = ".tmin_tmp";
+
+// Keep only the bytes that flow into some comparisons.
+fn prune_untainted<F>(buf: &[u8], tainted: &HashSet<usize>, check: &mut F) -> Vec<u8>
+where
+    F: FnMut(&Vec<u8>) -> bool,
+{
+    let pruned: Vec<u8> = buf
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| tainted.contains(i))
+        .map(|(_, &v)| v)
+        .collect();
+    if pruned.len() < buf.len() && check(&pruned) {
+        pruned
+    } else {
+        buf.to_vec()
+    }
+}
+
+// Remove blocks from large to small.
+fn delete_blocks<F>(buf: &[u8], check: &mut F) -> Vec<u8>
+where
+    F: FnMut(&Vec<u8>) -> bool,
+{
+    let mut buf = buf.to_vec();
+    let mut block = std::cmp::max(buf.len() / config::TMIN_BLOCK_STEPS, 1);
+    loop {
+        let mut changed = false;
+        let mut i = 0;
+        while i < buf.len() {
+            let end = std::cmp::min(i + block, buf.len());
+            let mut tmp = buf.clone();
+            tmp.drain(i..end);
+            if check(&tmp) {
+                buf = tmp;
+                changed = true;
+            } else {
+                i += block;
+            }
+        }
+        if !changed {
+            if block == 1 {
+                break;
+            }
+            block = std::cmp::max(block / 2, 1);
+        }
+    }
+    buf
+}
+
+// Replace the bytes with a fixed one, so that the rest ones stand out.
+fn normalize_bytes<F>(buf: &[u8], check: &mut F) -> Vec<u8>
+where
+    F: FnMut(&Vec<u8>) -> bool,
+{
+    let all = vec![config::TMIN_NORMAL_BYTE; buf.len()];
+    if check(&all) {
+        return all;
+    }
+    let mut buf = buf.to_vec();
+    for i in 0..buf.len() {
+        if buf[i] == config::TMIN_NORMAL_BYTE {
+            continue;
+        }
+        let mut tmp = buf.clone();
+        tmp[i] = config::TMIN_NORMAL_BYTE;
+        if check(&tmp) {
+            buf = tmp;
+        }
+    }
+    buf
+}
+
+fn minimize<F>(buf: &[u8], tainted: Option<&HashSet<usize>>, check: &mut F) -> Vec<u8>
+where
+    F: FnMut(&Vec<u8>) -> bool,
+{
+    let mut buf = buf.to_vec();
+    if let Some(tainted) = tainted {
+        buf = prune_untainted(&buf, tainted, check);
+    }
+    // Deleting may succeed again after normalizing.
+    loop {
+        let len = buf.len();
+        buf = delete_blocks(&buf, check);
+        buf = normalize_bytes(&buf, check);
+        if buf.len() == len {
+            break;
+        }
+    }
+    buf
+}
+
+pub fn tmin_main(
+    in_file: &str,
+    out_file: &str,
+    pargs: Vec<String>,
+    track_target: Option<&str>,
+    mem_limit: u64,
+    time_limit: u64,
+    exact_trace: bool,
+) {
+    pretty_env_logger::init();
+
+    let buf = fs::read(in_file).expect("Could not read input file.");
+    let tmp_dir = Path::new(out_file)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(TMP_DIR);
+    let mut runner = TraceRunner::new(&pargs, track_target, tmp_dir, mem_limit, time_limit);
+
+    let (status, trace) = runner.run(&buf);
+    info!("The status of the input is {:?}", status);
+    let exact_trace = exact_trace || status == StatusType::Normal;
+    if status == StatusType::Error {
+        error!("FATAL: Could not run the program.");
+        panic!();
+    }
+
+    let tainted = if runner.has_track() {
+        let conds = runner.track(&buf);
+        let mut tainted = HashSet::new();
+        for cond in &conds {
+            for off in &cond.offsets {
+                tainted.extend(off.begin as usize..off.end as usize);
+            }
+        }
+        info!("{} of {} bytes are tainted.", tainted.len(), buf.len());
+        Some(tainted)
+    } else {
+        None
+    };
+
+    let mut num_exec = 0;
+    let mut check = |b: &Vec<u8>| {
+        num_exec += 1;
+        let (s, t) = runner.run(b);
+        s == status && (!exact_trace || t == trace)
+    };
+    let min_buf = minimize(&buf, tainted.as_ref(), &mut check);
+
+    fs::write(out_file, &min_buf).expect("Could not write output file.");
+    println!(
+        "Shrink from {} to {} bytes, with {} executions.",
+        buf.len(),
+        min_buf.len(),
+        num_exec
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize() {
+        // "crash" if it has 'B' after 'A'
+        let mut check = |b: &Vec<u8>| match b.iter().position(|&x| x == b'A') {
+            Some(i) => b[i..].contains(&b'B'),
+            None => false,
+        };
+        let buf = b"xxxxAyyyyyyBzzzzzzzzzzzzzzz".to_vec();
+        assert_eq!(minimize(&buf, None, &mut check), b"AB".to_vec());
+
+        let tainted: HashSet<usize> = vec![1, 4, 11].into_iter().collect();
+        assert_eq!(prune_untainted(&buf, &tainted, &mut check), b"xAB".to_vec());
+        let tainted: HashSet<usize> = vec![4].into_iter().collect();
+        assert_eq!(prune_untainted(&buf, &tainted, &mut check), buf);
+    }
+}
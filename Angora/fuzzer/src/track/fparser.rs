@@ -2,25 +2,34 @@ use super::filter;
 use super::load_pin_data::get_log_data_pin;
 use crate::{
     cond_stmt::{CondState, CondStmt},
-    mut_input,
+    mut_input::{self, LenField},
 };
-use angora_common::{defs, tag::TagSeg};
+use angora_common::{defs, log_data::LogData, tag::TagSeg};
 use runtime::get_log_data;
-use std::{collections::HashMap, io, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::Path,
+};
+
+fn read_log_data(out_f: &Path, is_pin_mode: bool) -> io::Result<LogData> {
+    if is_pin_mode {
+        get_log_data_pin(out_f)
+    } else {
+        get_log_data(out_f)
+    }
+}
 
 pub fn read_and_parse(
     out_f: &Path,
     is_pin_mode: bool,
     enable_exploitation: bool,
 ) -> io::Result<Vec<CondStmt>> {
-    let log_data = {
-        if is_pin_mode {
-            get_log_data_pin(out_f)?
-        } else {
-            get_log_data(out_f)?
-        }
-    };
+    let log_data = read_log_data(out_f, is_pin_mode)?;
+    Ok(parse_cond_list(&log_data, enable_exploitation))
+}
 
+fn parse_cond_list(log_data: &LogData, enable_exploitation: bool) -> Vec<CondStmt> {
     let mut cond_list: Vec<CondStmt> = Vec::new();
     // assign taint labels and magic_bytes to cond list
     for (i, cond_base) in log_data.cond_list.iter().enumerate() {
@@ -39,7 +48,49 @@ pub fn read_and_parse(
 
         cond_list.push(cond);
     }
-    Ok(cond_list)
+    cond_list
+}
+
+// Locate the length fields in `buf` with the values seen by the tracker.
+fn parse_len_fields(log_data: &LogData, buf: &[u8]) -> Vec<LenField> {
+    let mut fields = vec![];
+    let mut visited = HashSet::new();
+    for lf in &log_data.len_fields {
+        let offsets = match log_data.tags.get(&lf.lb) {
+            Some(v) => v,
+            None => continue,
+        };
+        // Only a continuous field is considered.
+        if offsets.len() != 1 || lf.read_unit == 0 {
+            continue;
+        }
+        let width = offsets[0].end - offsets[0].begin;
+        if width == 0 || width > 8 {
+            continue;
+        }
+        let val = if width < 8 {
+            lf.val & ((1 << (width * 8)) - 1)
+        } else {
+            lf.val
+        };
+        let mut field = LenField {
+            begin: offsets[0].begin,
+            width,
+            data_begin: lf.read_offset,
+            unit: lf.read_unit,
+            big_endian: false,
+        };
+        if field.read(buf) != Some(val) {
+            field.big_endian = true;
+            if field.read(buf) != Some(val) {
+                continue;
+            }
+        }
+        if visited.insert((field.begin, field.data_begin)) {
+            fields.push(field);
+        }
+    }
+    fields
 }
 
 fn get_offsets_and_variables(
@@ -80,12 +131,16 @@ pub fn load_track_data(
     speed: u32,
     is_pin_mode: bool,
     enable_exploitation: bool,
-) -> Vec<CondStmt> {
-    let mut cond_list = match read_and_parse(out_f, is_pin_mode, enable_exploitation) {
-        Result::Ok(val) => val,
+    buf: &[u8],
+) -> (Vec<CondStmt>, Vec<LenField>) {
+    let (mut cond_list, len_fields) = match read_log_data(out_f, is_pin_mode) {
+        Result::Ok(log_data) => (
+            parse_cond_list(&log_data, enable_exploitation),
+            parse_len_fields(&log_data, buf),
+        ),
         Result::Err(err) => {
             error!("parse track file error!! {:?}", err);
-            vec![]
+            (vec![], vec![])
         }
     };
 
@@ -99,5 +154,5 @@ pub fn load_track_data(
 
     filter::filter_cond_list(&mut cond_list);
 
-    cond_list
+    (cond_list, len_fields)
 }
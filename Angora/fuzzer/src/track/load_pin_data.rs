@@ -67,5 +67,6 @@ pub fn get_log_data_pin(out_f: &Path) -> io::Result<LogData> {
         cond_list,
         tags: tags_map,
         magic_bytes: mb_map,
+        len_fields: vec![],
     })
 }
@@ -0,0 +1,320 @@
+// Crash triage: re-run the crashes with symbolized sanitizers,
+// and bucket them by the bug type and the top frames of the stack.
+use crate::{
+    branches::GlobalBranches, command::CommandOpt, depot, depot::Depot, executor::Executor,
+    search::Dict, stats,
+};
+use angora_common::{config, defs};
+use serde_derive::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread, time,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// FNV-1a, so the hashes in triage.json are the same across runs and builds.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |h, &b| (h ^ u64::from(b)).wrapping_mul(FNV_PRIME))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CrashReport {
+    pub bug_type: String,
+    pub frames: Vec<String>,
+}
+
+impl CrashReport {
+    // The frames in the sanitizers' runtime are not the bug's.
+    fn is_runtime_frame(func: &str) -> bool {
+        const PREFIXES: [&str; 7] = [
+            "__asan",
+            "__msan",
+            "__ubsan",
+            "__tsan",
+            "__lsan",
+            "__sanitizer",
+            // __interceptor and __interception
+            "__intercept",
+        ];
+        func == "<null>" || PREFIXES.iter().any(|p| func.starts_with(p))
+    }
+
+    // The first words of the message, without the addresses, sizes and types,
+    // e.g. "signed integer overflow: 2147483647 + 1 cannot be .." is signed-integer-overflow.
+    fn bug_type(msg: &str, max_words: usize) -> String {
+        let msg = msg.split(&[':', '('][..]).next().unwrap_or("");
+        let words: Vec<&str> = msg
+            .split_whitespace()
+            .filter(|w| !w.chars().any(|c| c.is_ascii_digit() || c == '\''))
+            .take(max_words)
+            .collect();
+        words.join("-")
+    }
+
+    pub fn parse(report: &str) -> Self {
+        // The header of each sanitizer, and how many words of it are the bug type.
+        // ==1==ERROR: AddressSanitizer: heap-buffer-overflow on address ...
+        // ==1==WARNING: MemorySanitizer: use-of-uninitialized-value
+        // WARNING: ThreadSanitizer: data race (pid=1)
+        // ==1==ERROR: LeakSanitizer: detected memory leaks
+        // /src/lib/math.c:7:12: runtime error: signed integer overflow: ...
+        const HEADERS: [(&str, usize); 5] = [
+            ("AddressSanitizer: ", 1),
+            ("MemorySanitizer: ", 1),
+            ("ThreadSanitizer: ", 4),
+            ("LeakSanitizer: ", 4),
+            ("runtime error: ", 4),
+        ];
+        let mut bug_type = String::from("unknown");
+        let mut frames = vec![];
+        for line in report.lines() {
+            let line = line.trim();
+            if bug_type == "unknown" {
+                for (header, max_words) in HEADERS.iter() {
+                    if let Some(pos) = line.find(header) {
+                        let t = Self::bug_type(&line[pos + header.len()..], *max_words);
+                        if !t.is_empty() {
+                            bug_type = t;
+                            break;
+                        }
+                    }
+                }
+            }
+            // #0 0x4f2b1c in parse_header /src/lib/header.c:42:3
+            // #0 parse_header /src/lib/header.c:42:3 (prog+0x4f2b1c), by TSan
+            if line.starts_with('#') {
+                let mut it = line.split_whitespace();
+                let idx = it.next().unwrap_or("#0");
+                // The next stacks, e.g. where it is allocated.
+                if idx == "#0" && !frames.is_empty() {
+                    break;
+                }
+                let func = match (it.next(), it.next()) {
+                    (Some(addr), Some("in")) if addr.starts_with("0x") => it.next(),
+                    (Some(func), _) if !func.starts_with("0x") => Some(func),
+                    _ => None,
+                };
+                if let Some(func) = func {
+                    if !Self::is_runtime_frame(func) {
+                        frames.push(func.to_string());
+                    }
+                }
+            }
+        }
+        frames.truncate(config::TRIAGE_TOP_FRAMES);
+        Self { bug_type, frames }
+    }
+
+    pub fn stack_hash(&self) -> u64 {
+        // Each name ends with a 0, so that ("ab", "c") and ("a", "bc") differ.
+        let mut hash = FNV_OFFSET_BASIS;
+        for name in std::iter::once(&self.bug_type).chain(self.frames.iter()) {
+            hash = fnv1a(hash, name.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+        hash
+    }
+}
+
+#[derive(Serialize)]
+struct Bucket {
+    hash: String,
+    bug_type: String,
+    frames: Vec<String>,
+    crashes: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct CrashTriage {
+    triaged: HashSet<String>,
+    buckets: HashMap<u64, Bucket>,
+}
+
+impl CrashTriage {
+    pub fn num_unique(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // Triage the crashes saved since the last time, until it stops running.
+    pub fn triage_new(&mut self, executor: &mut Executor, dir: &Path, running: &AtomicBool) {
+        let mut names: Vec<String> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .filter(|n| {
+                    n.starts_with("id:")
+                        && !n.ends_with(defs::OUTPUT_LOG_SUFFIX)
+                        && !self.triaged.contains(n)
+                })
+                .collect(),
+            Err(_) => return,
+        };
+        if names.is_empty() {
+            return;
+        }
+        names.sort();
+        for name in names {
+            if !running.load(Ordering::Relaxed) {
+                break;
+            }
+            let path = dir.join(&name);
+            let buf = depot::read_from_file(&path);
+            let (status, report) = executor.run_with_report(&buf);
+            let mut c = CrashReport::parse(&report);
+            if c.frames.is_empty() {
+                // Not built with sanitizers, or it is flaky.
+                c.bug_type = format!("{:?}", status).to_lowercase();
+            }
+            let hash = c.stack_hash();
+            self.triaged.insert(name.clone());
+            self.buckets
+                .entry(hash)
+                .or_insert_with(|| Bucket {
+                    hash: format!("{:016x}", hash),
+                    bug_type: c.bug_type,
+                    frames: c.frames,
+                    crashes: vec![],
+                })
+                .crashes
+                .push(name);
+        }
+        self.save(dir);
+    }
+
+    fn save(&self, dir: &Path) {
+        let mut buckets: Vec<&Bucket> = self.buckets.values().collect();
+        buckets.sort_by(|a, b| a.crashes[0].cmp(&b.crashes[0]));
+        let index = serde_json::json!({
+            "unique_bugs": buckets.len(),
+            "buckets": buckets,
+        });
+        if let Err(e) = fs::write(dir.join(defs::TRIAGE_FILE), index.to_string()) {
+            warn!("Could not write triage file: {:?}", e);
+        }
+    }
+}
+
+// Triage in its own thread and executor, since each crash is re-run
+// for up to `time_limit_track` seconds.
+pub fn triage_loop(
+    running: Arc<AtomicBool>,
+    cmd_opt: CommandOpt,
+    depot: Arc<Depot>,
+    global_branches: Arc<GlobalBranches>,
+    global_stats: Arc<RwLock<stats::ChartStats>>,
+    dictionary: Arc<RwLock<Dict>>,
+) {
+    let mut executor = Executor::new(
+        cmd_opt,
+        global_branches,
+        depot.clone(),
+        global_stats.clone(),
+        dictionary,
+    );
+    let mut triage = CrashTriage::default();
+    while running.load(Ordering::Relaxed) {
+        triage.triage_new(&mut executor, &depot.dirs.crashes_dir, &running);
+        global_stats.write().unwrap().set_unique_crashes(triage.num_unique());
+        thread::sleep(time::Duration::from_secs(5));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report() {
+        let report = r#"
+=================================================================
+==4242==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000015 at pc 0x4f2b1c bp 0x7ffc sp 0x7ffc
+READ of size 1 at 0x602000000015 thread T0
+    #0 0x4f2b1c in parse_header /src/lib/header.c:42:3
+    #1 0x4f3000 in parse_file /src/lib/file.c:10:5
+    #2 0x4f4000 in main /src/main.c:7:3
+    #3 0x7f00 in __libc_start_main (/lib/x86_64-linux-gnu/libc.so.6+0x21b96)
+
+0x602000000015 is located 0 bytes to the right of 5-byte region
+allocated by thread T0 here:
+    #0 0x4c3a in __interceptor_malloc (/out/prog+0x4c3a)
+    #1 0x4f2a00 in read_all /src/lib/io.c:3:9
+"#;
+        let c = CrashReport::parse(report);
+        assert_eq!(c.bug_type, "heap-buffer-overflow");
+        assert_eq!(c.frames, vec!["parse_header", "parse_file", "main"]);
+
+        let other = CrashReport::parse(&report.replace("parse_file", "parse_stream"));
+        assert_ne!(c.stack_hash(), other.stack_hash());
+        // It is stable, for the buckets of a resumed run.
+        assert_eq!(c.stack_hash(), CrashReport::parse(report).stack_hash());
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"a"), 0xaf63_dc4c_8601_ec8c);
+        // The allocation stack is not the bug's.
+        let leak = "==9==ERROR: LeakSanitizer: detected memory leaks\n\nDirect leak of 7 byte(s) in 1 object(s) allocated from:\n    #0 0x4c3a in malloc (/out/prog+0x4c3a)\n    #1 0x4f2a00 in read_all /src/lib/io.c:3:9\n";
+        let c = CrashReport::parse(leak);
+        assert_eq!(c.bug_type, "detected-memory-leaks");
+        assert_eq!(c.frames, vec!["malloc", "read_all"]);
+        assert_eq!(
+            CrashReport::parse("Segmentation fault"),
+            CrashReport {
+                bug_type: "unknown".to_string(),
+                frames: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_ubsan_report() {
+        let report = r#"
+/src/lib/math.c:7:12: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+    #0 0x4c1234 in add /src/lib/math.c:7:12
+    #1 0x4c2000 in eval /src/lib/eval.c:20:9
+    #2 0x4c3000 in main /src/main.c:7:3
+    #3 0x7f00 in __libc_start_main (/lib/x86_64-linux-gnu/libc.so.6+0x21b96)
+    #4 0x41c029 in _start (/out/prog+0x41c029)
+
+SUMMARY: UndefinedBehaviorSanitizer: undefined-behavior /src/lib/math.c:7:12 in
+"#;
+        let c = CrashReport::parse(report);
+        assert_eq!(c.bug_type, "signed-integer-overflow");
+        assert_eq!(c.frames, vec!["add", "eval", "main"]);
+
+        let c = CrashReport::parse(
+            "/src/a.c:3:10: runtime error: index 10 out of bounds for type 'int [5]'\n    #0 0x4c1234 in get /src/a.c:3:10\n",
+        );
+        assert_eq!(c.bug_type, "index-out-of-bounds");
+        assert_eq!(c.frames, vec!["get"]);
+    }
+
+    #[test]
+    fn test_parse_tsan_report() {
+        let report = r#"
+==================
+WARNING: ThreadSanitizer: data race (pid=4242)
+  Write of size 4 at 0x7b0400000000 by thread T1:
+    #0 __tsan_write4 <null> (prog+0x4a1000)
+    #1 incr /src/lib/counter.c:5:11 (prog+0x4b1c2a)
+    #2 worker /src/lib/pool.c:30:5 (prog+0x4b1d00)
+    #3 <null> <null> (libc.so.6+0x94ac2)
+
+  Previous write of size 4 at 0x7b0400000000 by main thread:
+    #0 incr /src/lib/counter.c:5:11 (prog+0x4b1c2a)
+    #1 main /src/main.c:12:3 (prog+0x4b1e00)
+
+SUMMARY: ThreadSanitizer: data race /src/lib/counter.c:5:11 in incr
+==================
+"#;
+        let c = CrashReport::parse(report);
+        assert_eq!(c.bug_type, "data-race");
+        assert_eq!(c.frames, vec!["incr", "worker"]);
+    }
+}
@@ -141,6 +141,11 @@ static void add_angora_pass() {
 static void add_angora_runtime() {
   // cc_params[cc_par_cnt++] = "-I/${HOME}/clang+llvm/include/c++/v1";
   if (clang_type == CLANG_FAST_TYPE) {
+    if (getenv(SHM_READ_VAR)) {
+      cc_params[cc_par_cnt++] = "-Wl,--whole-archive";
+      cc_params[cc_par_cnt++] = alloc_printf("%s/lib/libShmRead.a", obj_path);
+      cc_params[cc_par_cnt++] = "-Wl,--no-whole-archive";
+    }
     cc_params[cc_par_cnt++] = alloc_printf("%s/lib/libruntime_fast.a", obj_path);
   } else if (clang_type == CLANG_TRACK_TYPE || clang_type == CLANG_DFSAN_TYPE) {
     cc_params[cc_par_cnt++] = "-Wl,--whole-archive";
@@ -338,7 +343,6 @@ static void edit_params(u32 argc, char **argv) {
 
    */
 
-  /*
   cc_params[cc_par_cnt++] = "-D__ANGORA_LOOP(_A)="
     "({ static volatile char *_B __attribute__((used)); "
     " _B = (char*)\"" PERSIST_SIG "\"; "
@@ -362,7 +366,6 @@ static void edit_params(u32 argc, char **argv) {
     "void _I(void) __asm__(\"__angora_manual_init\"); "
 #endif
     "_I(); } while (0)";
-  */
 
   if (is_cxx) {
     // FIXME: or use the same header
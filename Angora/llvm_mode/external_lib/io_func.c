@@ -4,8 +4,13 @@
   to write custom functions, modify custom/angora_abilist.txt first
  */
 
+#ifndef _GNU_SOURCE
+#define _GNU_SOURCE // accept4
+#endif
+#include <arpa/inet.h>
 #include <assert.h>
 #include <fcntl.h>
+#include <netinet/in.h>
 #include <stdarg.h>
 #include <stdbool.h>
 #include <stdint.h>
@@ -13,6 +18,7 @@
 #include <stdlib.h>
 #include <string.h>
 #include <sys/mman.h>
+#include <sys/socket.h>
 #include <sys/stat.h>
 #include <time.h>
 #include <unistd.h>
@@ -38,6 +44,22 @@ __attribute__((destructor(0))) void __angora_track_fini(void) {
 #define remove_fuzzing_fd __angora_io_remove_fd
 #define remove_fuzzing_ffd __angora_io_remove_pfile
 
+// network input, see runtime/src/ffds.rs
+extern void __angora_io_add_sock(int fd);
+extern uint32_t __angora_io_is_net_port(uint16_t port);
+extern long __angora_io_sock_read(int fd, ssize_t len);
+#define add_fuzzing_sock __angora_io_add_sock
+#define is_net_port __angora_io_is_net_port
+#define sock_read __angora_io_sock_read
+
+// multi-file input, see runtime/src/ffds.rs
+extern void __angora_io_add_part(int fd, const char *path);
+extern long __angora_io_part_offset(int fd);
+#define add_fuzzing_part __angora_io_add_part
+#define part_offset __angora_io_part_offset
+// The offset in the test case, the file may be one of its parts.
+#define input_ftell(f) (ftell(f) + part_offset(fileno(f)))
+
 static void assign_taint_labels(void *buf, long offset, size_t size) {
   for (size_t i = 0; i < size; i += granularity) {
     // start from 0
@@ -66,6 +88,35 @@ static void assign_taint_labels_exf(void *buf, long offset, size_t ret,
   assign_taint_labels(buf, offset, len);
 }
 
+// The slices of the input in argv and environ, set by the fuzzer
+// in TAINT_ARGS_VAR as IDX:POS:OFFSET:LEN; and TAINT_ENVS_VAR as NAME:POS:OFFSET:LEN;
+__attribute__((constructor)) static void
+taint_arg_input(int argc, char **argv, char **envp) {
+  const char *spec = getenv(TAINT_ARGS_VAR);
+  int idx;
+  size_t pos, len;
+  long off;
+  int n = 0;
+  while (spec && sscanf(spec, "%d:%zu:%ld:%zu;%n", &idx, &pos, &off, &len, &n) == 4 &&
+         n > 0) {
+    if (idx > 0 && idx < argc && pos + len <= strlen(argv[idx]))
+      assign_taint_labels(argv[idx] + pos, off, len);
+    spec += n;
+    n = 0;
+  }
+  spec = getenv(TAINT_ENVS_VAR);
+  char name[256];
+  n = 0;
+  while (spec && sscanf(spec, "%255[^:]:%zu:%ld:%zu;%n", name, &pos, &off, &len, &n) == 4 &&
+         n > 0) {
+    char *val = getenv(name);
+    if (val && pos + len <= strlen(val))
+      assign_taint_labels(val + pos, off, len);
+    spec += n;
+    n = 0;
+  }
+}
+
 #define IS_FUZZING_FILE(filename) strstr(filename, FUZZING_INPUT_FILE)
 
 __attribute__((visibility("default"))) int
@@ -89,6 +140,7 @@ __dfsw_open(const char *path, int oflags, dfsan_label path_label,
 
   if (fd >= 0 && IS_FUZZING_FILE(path)) {
     add_fuzzing_fd(fd);
+    add_fuzzing_part(fd, path);
   }
 
   *ret_label = 0;
@@ -107,6 +159,7 @@ __dfsw_fopen(const char *filename, const char *mode, dfsan_label fn_label,
 
   if (fd && IS_FUZZING_FILE(filename)) {
     add_fuzzing_ffd(fd);
+    add_fuzzing_part(fileno(fd), filename);
   }
 
   *ret_label = 0;
@@ -124,6 +177,7 @@ __dfsw_fopen64(const char *filename, const char *mode, dfsan_label fn_label,
 
   if (fd && IS_FUZZING_FILE(filename)) {
     add_fuzzing_ffd(fd);
+    add_fuzzing_part(fileno(fd), filename);
   }
 
   *ret_label = 0;
@@ -172,7 +226,7 @@ __dfsw_mmap(void *start, size_t length, int prot, int flags, int fd,
 #endif
   void *ret = mmap(start, length, prot, flags, fd, offset);
   if (ret > 0 && is_fuzzing_fd(fd)) {
-    assign_taint_labels(ret, offset, length);
+    assign_taint_labels(ret, offset + part_offset(fd), length);
   }
   *ret_label = 0;
   return ret;
@@ -199,7 +253,7 @@ __dfsw_fread(void *buf, size_t size, size_t count, FILE *fd,
              dfsan_label buf_label, dfsan_label size_label,
              dfsan_label count_label, dfsan_label fd_label,
              dfsan_label *ret_label) {
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   size_t ret = fread(buf, size, count, fd);
 #ifdef DEBUG_INFO
   fprintf(stderr, "### fread %p,range is %ld, %ld  --  (size %d, count %d)\n",
@@ -220,7 +274,7 @@ __dfsw_fread_unlocked(void *buf, size_t size, size_t count, FILE *fd,
                       dfsan_label buf_label, dfsan_label size_label,
                       dfsan_label count_label, dfsan_label fd_label,
                       dfsan_label *ret_label) {
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   size_t ret = fread_unlocked(buf, size, count, fd);
 #ifdef DEBUG_INFO
   fprintf(stderr, "### fread_unlocked %p,range is %ld, %ld/%ld\n", fd, offset,
@@ -241,8 +295,11 @@ __dfsw_read(int fd, void *buf, size_t count, dfsan_label fd_label,
             dfsan_label buf_label, dfsan_label count_label,
             dfsan_label *ret_label) {
 
-  long offset = lseek(fd, 0, SEEK_CUR);
+  long offset = lseek(fd, 0, SEEK_CUR) + part_offset(fd);
   ssize_t ret = read(fd, buf, count);
+  long sock_offset = sock_read(fd, ret);
+  if (sock_offset >= 0)
+    offset = sock_offset;
 #ifdef DEBUG_INFO
   fprintf(stderr, "### read %d, range is %ld, %ld/%ld \n", fd, offset, ret,
           count);
@@ -257,6 +314,107 @@ __dfsw_read(int fd, void *buf, size_t count, dfsan_label fd_label,
   return ret;
 }
 
+static int is_net_addr(const struct sockaddr *addr) {
+  if (!addr)
+    return 0;
+  if (addr->sa_family == AF_INET)
+    return is_net_port(ntohs(((const struct sockaddr_in *)addr)->sin_port));
+  if (addr->sa_family == AF_INET6)
+    return is_net_port(ntohs(((const struct sockaddr_in6 *)addr)->sin6_port));
+  return 0;
+}
+
+__attribute__((visibility("default"))) int
+__dfsw_bind(int sockfd, const struct sockaddr *addr, socklen_t addrlen,
+            dfsan_label sockfd_label, dfsan_label addr_label,
+            dfsan_label addrlen_label, dfsan_label *ret_label) {
+  int ret = bind(sockfd, addr, addrlen);
+#ifdef DEBUG_INFO
+  fprintf(stderr, "### bind %d, ret is %d \n", sockfd, ret);
+#endif
+  if (ret == 0 && is_net_addr(addr)) {
+    add_fuzzing_sock(sockfd);
+  }
+  *ret_label = 0;
+  return ret;
+}
+
+__attribute__((visibility("default"))) int
+__dfsw_accept(int sockfd, struct sockaddr *addr, socklen_t *addrlen,
+              dfsan_label sockfd_label, dfsan_label addr_label,
+              dfsan_label addrlen_label, dfsan_label *ret_label) {
+  int fd = accept(sockfd, addr, addrlen);
+#ifdef DEBUG_INFO
+  fprintf(stderr, "### accept %d, fd is %d \n", sockfd, fd);
+#endif
+  if (fd >= 0 && is_fuzzing_fd(sockfd)) {
+    add_fuzzing_sock(fd);
+  }
+  *ret_label = 0;
+  return fd;
+}
+
+__attribute__((visibility("default"))) int
+__dfsw_accept4(int sockfd, struct sockaddr *addr, socklen_t *addrlen,
+               int flags, dfsan_label sockfd_label, dfsan_label addr_label,
+               dfsan_label addrlen_label, dfsan_label flags_label,
+               dfsan_label *ret_label) {
+  int fd = accept4(sockfd, addr, addrlen, flags);
+#ifdef DEBUG_INFO
+  fprintf(stderr, "### accept4 %d, fd is %d \n", sockfd, fd);
+#endif
+  if (fd >= 0 && is_fuzzing_fd(sockfd)) {
+    add_fuzzing_sock(fd);
+  }
+  *ret_label = 0;
+  return fd;
+}
+
+__attribute__((visibility("default"))) ssize_t
+__dfsw_recv(int sockfd, void *buf, size_t len, int flags,
+            dfsan_label sockfd_label, dfsan_label buf_label,
+            dfsan_label len_label, dfsan_label flags_label,
+            dfsan_label *ret_label) {
+  ssize_t ret = recv(sockfd, buf, len, flags);
+  // Peeking does not consume the data.
+  long offset = sock_read(sockfd, (flags & MSG_PEEK) ? 0 : ret);
+#ifdef DEBUG_INFO
+  fprintf(stderr, "### recv %d, range is %ld, %ld/%ld \n", sockfd, offset,
+          ret, len);
+#endif
+  if (offset >= 0) {
+    if (ret > 0)
+      assign_taint_labels_exf(buf, offset, ret, len, 1);
+    *ret_label = __angora_get_sp_label(offset, 1);
+  } else {
+    *ret_label = 0;
+  }
+  return ret;
+}
+
+__attribute__((visibility("default"))) ssize_t
+__dfsw_recvfrom(int sockfd, void *buf, size_t len, int flags,
+                struct sockaddr *src_addr, socklen_t *addrlen,
+                dfsan_label sockfd_label, dfsan_label buf_label,
+                dfsan_label len_label, dfsan_label flags_label,
+                dfsan_label src_addr_label, dfsan_label addrlen_label,
+                dfsan_label *ret_label) {
+  ssize_t ret = recvfrom(sockfd, buf, len, flags, src_addr, addrlen);
+  long offset = sock_read(sockfd, (flags & MSG_PEEK) ? 0 : ret);
+#ifdef DEBUG_INFO
+  fprintf(stderr, "### recvfrom %d, range is %ld, %ld/%ld \n", sockfd, offset,
+          ret, len);
+#endif
+  if (offset >= 0) {
+    if (ret > 0)
+      assign_taint_labels_exf(buf, offset, ret, len, 1);
+    *ret_label = __angora_get_sp_label(offset, 1);
+  } else {
+    *ret_label = 0;
+  }
+  return ret;
+}
+
 __attribute__((visibility("default"))) ssize_t
 __dfsw_pread(int fd, void *buf, size_t count, off_t offset,
              dfsan_label fd_label, dfsan_label buf_label,
@@ -268,9 +426,10 @@ __dfsw_pread(int fd, void *buf, size_t count, off_t offset,
           count);
 #endif
   if (is_fuzzing_fd(fd)) {
+    long in_offset = offset + part_offset(fd);
     if (ret > 0)
-      assign_taint_labels_exf(buf, offset, ret, count, 1);
-    *ret_label = __angora_get_sp_label(offset, 1);
+      assign_taint_labels_exf(buf, in_offset, ret, count, 1);
+    *ret_label = __angora_get_sp_label(in_offset, 1);
   } else {
     *ret_label = 0;
   }
@@ -280,7 +439,7 @@ __dfsw_pread(int fd, void *buf, size_t count, off_t offset,
 __attribute__((visibility("default"))) int
 __dfsw_fgetc(FILE *fd, dfsan_label fd_label, dfsan_label *ret_label) {
 
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   int c = fgetc(fd);
   *ret_label = 0;
 #ifdef DEBUG_INFO
@@ -295,7 +454,7 @@ __dfsw_fgetc(FILE *fd, dfsan_label fd_label, dfsan_label *ret_label) {
 __attribute__((visibility("default"))) int
 __dfsw_fgetc_unlocked(FILE *fd, dfsan_label fd_label, dfsan_label *ret_label) {
 
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   int c = fgetc_unlocked(fd);
   *ret_label = 0;
 #ifdef DEBUG_INFO
@@ -310,7 +469,7 @@ __dfsw_fgetc_unlocked(FILE *fd, dfsan_label fd_label, dfsan_label *ret_label) {
 
 __attribute__((visibility("default"))) int
 __dfsw__IO_getc(FILE *fd, dfsan_label fd_label, dfsan_label *ret_label) {
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   int c = getc(fd);
   *ret_label = 0;
 #ifdef DEBUG_INFO
@@ -345,7 +504,7 @@ __dfsw_fgets(char *str, int count, FILE *fd, dfsan_label str_label,
              dfsan_label count_label, dfsan_label fd_label,
              dfsan_label *ret_label) {
 
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   char *ret = fgets(str, count, fd);
 #ifdef DEBUG_INFO
   fprintf(stderr, "fgets %p, range is %ld, %ld \n", fd, offset, strlen(ret));
@@ -366,7 +525,7 @@ __dfsw_fgets_unlocked(char *str, int count, FILE *fd, dfsan_label str_label,
                       dfsan_label count_label, dfsan_label fd_label,
                       dfsan_label *ret_label) {
 
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   char *ret = fgets_unlocked(str, count, fd);
 #ifdef DEBUG_INFO
   fprintf(stderr, "fgets_unlocked %p, range is %ld, %ld \n", fd, offset,
@@ -428,7 +587,7 @@ __attribute__((visibility("default"))) ssize_t
 __dfsw_getline(char **lineptr, size_t *n, FILE *fd, dfsan_label buf_label,
                dfsan_label size_label, dfsan_label fd_label,
                dfsan_label *ret_label) {
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   ssize_t ret = getline(lineptr, n, fd);
 #ifdef DEBUG_INFO
   fprintf(stderr, "### getline %p,range is %ld, %ld\n", fd, offset, ret);
@@ -449,7 +608,7 @@ __dfsw_getdelim(char **lineptr, size_t *n, int delim, FILE *fd,
                 dfsan_label buf_label, dfsan_label size_label,
                 dfsan_label delim_label, dfsan_label fd_label,
                 dfsan_label *ret_label) {
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   ssize_t ret = getdelim(lineptr, n, delim, fd);
 #ifdef DEBUG_INFO
   fprintf(stderr, "### getdelim %p,range is %ld, %ld\n", fd, offset, ret);
@@ -466,7 +625,7 @@ __dfsw___getdelim(char **lineptr, size_t *n, int delim, FILE *fd,
                   dfsan_label buf_label, dfsan_label size_label,
                   dfsan_label delim_label, dfsan_label fd_label,
                   dfsan_label *ret_label) {
-  long offset = ftell(fd);
+  long offset = input_ftell(fd);
   ssize_t ret = __getdelim(lineptr, n, delim, fd);
 #ifdef DEBUG_INFO
   fprintf(stderr, "### __getdelim %p,range is %ld, %ld\n", fd, offset, ret);
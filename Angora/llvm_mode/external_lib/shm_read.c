@@ -0,0 +1,31 @@
+/*
+  Interpose read(2) on stdin, for the stdin targets that get the input
+  from shared memory (--shm_input).
+
+  Only linked with ANGORA_SHM_READ=1, since it takes the place of the read
+  of libc and of the sanitizers, e.g. MSan does not know the bytes are written.
+ */
+
+#ifndef _GNU_SOURCE
+#define _GNU_SOURCE // RTLD_NEXT
+#endif
+#include <dlfcn.h>
+#include <stddef.h>
+#include <unistd.h>
+
+// runtime_fast/src/shm_input.rs, -1 if the input is in the file.
+extern ssize_t __angora_shm_input_read(void *buf, size_t count);
+
+typedef ssize_t (*read_fn)(int, void *, size_t);
+
+ssize_t read(int fd, void *buf, size_t count) {
+  static read_fn next_read = NULL;
+  if (fd == STDIN_FILENO) {
+    ssize_t n = __angora_shm_input_read(buf, count);
+    if (n >= 0)
+      return n;
+  }
+  if (!next_read)
+    next_read = (read_fn)dlsym(RTLD_NEXT, "read");
+  return next_read(fd, buf, count);
+}
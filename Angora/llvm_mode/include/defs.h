@@ -28,6 +28,10 @@
 #define DEFER_ENV_VAR "ANGORA_DEFER_FORKSRV"
 #define PERSIST_SIG "##SIG_ANGORA_PERSISTENT##"
 #define DEFER_SIG "##SIG_ANGORA_DEFER_FORKSRV##"
+#define NET_PORT_VAR "ANGORA_NET_PORT"
+#define TAINT_ARGS_VAR "ANGORA_TAINT_ARGS"
+#define TAINT_ENVS_VAR "ANGORA_TAINT_ENVS"
+#define SHM_READ_VAR "ANGORA_SHM_READ"
 
 #define COND_EQ_OP 32
 #define COND_SW_TYPE 0x00FF
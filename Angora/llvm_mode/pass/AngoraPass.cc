@@ -323,7 +323,8 @@ void AngoraLLVMPass::initVariables(Module &M) {
       F->addAttribute(LLVM_ATTRIBUTE_LIST::FunctionIndex, Attribute::ReadOnly);
     }
 
-    Type *TraceExploitTtArgs[5] = {Int32Ty, Int32Ty, Int32Ty, Int32Ty, Int64Ty};
+    Type *TraceExploitTtArgs[7] = {Int32Ty, Int32Ty, Int32Ty, Int32Ty,
+                                   Int64Ty, Int64Ty, Int32Ty};
     TraceExploitTtTy = FunctionType::get(VoidTy, TraceExploitTtArgs, false);
     TraceExploitTT = M.getOrInsertFunction("__angora_trace_exploit_val_tt",
                                            TraceExploitTtTy);
@@ -827,6 +828,23 @@ void AngoraLLVMPass::visitExploitation(Instruction *Inst) {
       Type *ParamType = ParamVal->getType();
       if (ParamType->isIntegerTy() || ParamType->isPointerTy()) {
         if (!isa<ConstantInt>(ParamVal)) {
+          // The pointer the value indexes into, and the size of its element,
+          // so that the runtime can look up the bound of the allocation.
+          Value *BoundPtr = NULL;
+          uint64_t ElemSize = 0;
+          if (GetElementPtrInst *Gep = dyn_cast<GetElementPtrInst>(Inst)) {
+            if (i == 1) {
+              BoundPtr = Gep->getPointerOperand();
+              ElemSize = Inst->getModule()->getDataLayout().getTypeAllocSize(
+                  Gep->getSourceElementType());
+            }
+          } else if (Caller && i == 2 &&
+                     Caller->getArgOperand(0)->getType()->isPointerTy()) {
+            // memcpy, memset, memmove, ...
+            BoundPtr = Caller->getArgOperand(0);
+            ElemSize = 1;
+          }
+
           ConstantInt *Cid = ConstantInt::get(Int32Ty, getInstructionId(Inst));
           int size = ParamVal->getType()->getScalarSizeInBits() / 8;
           if (ParamType->isPointerTy()) {
@@ -839,8 +857,14 @@ void AngoraLLVMPass::visitExploitation(Instruction *Inst) {
           if (TrackMode) {
             LoadInst *CurCtx = IRB.CreateLoad(AngoraContext);
             setInsNonSan(CurCtx);
-            CallInst *ProxyCall = IRB.CreateCall(
-                TraceExploitTT, {Cid, CurCtx, SizeArg, TypeArg, ParamVal});
+            Value *PtrArg = ConstantInt::get(Int64Ty, 0);
+            if (BoundPtr && !BoundPtr->getType()->isVectorTy()) {
+              PtrArg = IRB.CreatePtrToInt(BoundPtr, Int64Ty);
+            }
+            Value *ElemSizeArg = ConstantInt::get(Int32Ty, ElemSize);
+            CallInst *ProxyCall =
+                IRB.CreateCall(TraceExploitTT, {Cid, CurCtx, SizeArg, TypeArg,
+                                                ParamVal, PtrArg, ElemSizeArg});
             setInsNonSan(ProxyCall);
           }
         }
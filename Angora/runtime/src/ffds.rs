@@ -1,6 +1,12 @@
+use angora_common::defs;
 use lazy_static::lazy_static;
 use libc;
-use std::{collections::HashSet, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    ffi::CStr,
+    sync::Mutex,
+};
 
 lazy_static! {
     static ref FFDS: Mutex<HashSet<u32>> = {
@@ -8,6 +14,18 @@ lazy_static! {
         set.insert(libc::STDIN_FILENO as u32);
         Mutex::new(set)
     };
+    // The sockets of the input can not be seeked, so we count what has been read.
+    static ref SOCK_OFFSETS: Mutex<HashMap<u32, i64>> = Mutex::new(HashMap::new());
+    // The port the fuzzer sends the input to.
+    static ref NET_PORT: Option<u16> = env::var(defs::NET_PORT_VAR)
+        .ok()
+        .and_then(|p| p.parse::<u16>().ok());
+    // The files of a multi-file input are parts of the test case,
+    // so the offsets in them start from where they are in it.
+    static ref PART_OFFSETS: Mutex<HashMap<u32, i64>> = Mutex::new(HashMap::new());
+    static ref INPUT_PARTS: Vec<i64> = env::var(defs::INPUT_PARTS_VAR)
+        .map(|s| s.split(',').filter_map(|o| o.parse::<i64>().ok()).collect())
+        .unwrap_or_default();
 }
 
 #[no_mangle]
@@ -26,6 +44,10 @@ pub extern "C" fn __angora_io_add_pfile(pfile: *mut libc::FILE) {
 pub extern "C" fn __angora_io_remove_fd(fd: libc::c_int) {
     let mut ffds = FFDS.lock().expect("Could not lock FFDS.");
     ffds.remove(&(fd as u32));
+    let mut offsets = SOCK_OFFSETS.lock().expect("Could not lock SOCK_OFFSETS.");
+    offsets.remove(&(fd as u32));
+    let mut parts = PART_OFFSETS.lock().expect("Could not lock PART_OFFSETS.");
+    parts.remove(&(fd as u32));
 }
 
 #[no_mangle]
@@ -45,3 +67,58 @@ pub extern "C" fn __angora_io_find_pfile(pfile: *mut libc::FILE) -> u32 {
     let fd = unsafe { libc::fileno(pfile) };
     __angora_io_find_fd(fd) as u32
 }
+
+// A socket bound to the port of ANGORA_NET_PORT, or accepted from it.
+#[no_mangle]
+pub extern "C" fn __angora_io_add_sock(fd: libc::c_int) {
+    __angora_io_add_fd(fd);
+    let mut offsets = SOCK_OFFSETS.lock().expect("Could not lock SOCK_OFFSETS.");
+    offsets.insert(fd as u32, 0);
+}
+
+#[no_mangle]
+pub extern "C" fn __angora_io_is_net_port(port: u16) -> u32 {
+    (*NET_PORT == Some(port)) as u32
+}
+
+// The offset of the `len` bytes just read from the socket,
+// or -1 if it is not a socket of the input.
+#[no_mangle]
+pub extern "C" fn __angora_io_sock_read(fd: libc::c_int, len: libc::ssize_t) -> libc::c_long {
+    let mut offsets = SOCK_OFFSETS.lock().expect("Could not lock SOCK_OFFSETS.");
+    match offsets.get_mut(&(fd as u32)) {
+        Some(off) => {
+            let cur = *off;
+            if len > 0 {
+                *off += len as i64;
+            }
+            cur as libc::c_long
+        },
+        None => -1,
+    }
+}
+
+// The file of the N-th part is named `<input file>.N`.
+fn part_of_path(path: &str) -> Option<usize> {
+    let (_, n) = path.rsplit_once('.')?;
+    n.parse::<usize>().ok().filter(|&n| n > 0)
+}
+
+#[no_mangle]
+pub extern "C" fn __angora_io_add_part(fd: libc::c_int, path: *const libc::c_char) {
+    if path.is_null() || INPUT_PARTS.is_empty() {
+        return;
+    }
+    let path = unsafe { CStr::from_ptr(path) }.to_string_lossy();
+    if let Some(off) = part_of_path(&path).and_then(|n| INPUT_PARTS.get(n - 1)) {
+        let mut parts = PART_OFFSETS.lock().expect("Could not lock PART_OFFSETS.");
+        parts.insert(fd as u32, *off);
+    }
+}
+
+// Where the file starts in the test case, 0 if it is not a part of it.
+#[no_mangle]
+pub extern "C" fn __angora_io_part_offset(fd: libc::c_int) -> libc::c_long {
+    let parts = PART_OFFSETS.lock().expect("Could not lock PART_OFFSETS.");
+    parts.get(&(fd as u32)).cloned().unwrap_or(0) as libc::c_long
+}
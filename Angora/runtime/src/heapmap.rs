@@ -1,14 +1,14 @@
 
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::sync::Mutex;
 
 type HeapPointer = usize;
 type HeapAreaBound = usize;
 
 lazy_static!{
-    static ref HEAPMAP: Mutex<HashMap<HeapPointer, HeapAreaBound>> = Mutex::new(
-        HashMap::new()
+    static ref HEAPMAP: Mutex<BTreeMap<HeapPointer, HeapAreaBound>> = Mutex::new(
+        BTreeMap::new()
     );
 }
 
@@ -50,6 +50,22 @@ fn heapmap_retrieve(base: HeapPointer) -> Option<HeapAreaBound> {
     Some(*ret)
 }
 
+// Find the allocation containing `ptr`, and return its base and bound.
+pub fn heapmap_find(ptr: HeapPointer) -> Option<(HeapPointer, HeapAreaBound)> {
+    let heapmap = match HEAPMAP.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            poisoned.into_inner()
+        },
+    };
+    let (base, bound) = heapmap.range(..=ptr).next_back()?;
+    if ptr < base + bound {
+        Some((*base, *bound))
+    } else {
+        None
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn heapmap_set(base: HeapPointer, bound: HeapAreaBound) {
     heapmap_insert(base, bound);
@@ -69,4 +85,20 @@ pub extern "C" fn heapmap_get(base: HeapPointer) -> HeapAreaBound {
         Some(b) => b,
         None => 0_usize,
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heapmap_find() {
+        heapmap_set(0x1000, 0x10);
+        heapmap_set(0x2000, 0x20);
+        assert_eq!(heapmap_find(0x1000), Some((0x1000, 0x10)));
+        assert_eq!(heapmap_find(0x200f), Some((0x2000, 0x20)));
+        assert_eq!(heapmap_find(0x1010), None);
+        assert_eq!(heapmap_find(0x10), None);
+        heapmap_invalidate(0x2000);
+        assert_eq!(heapmap_find(0x200f), None);
+    }
+}
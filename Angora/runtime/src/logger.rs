@@ -2,7 +2,11 @@ use bincode::{deserialize_from, serialize_into};
 use std::{collections::HashMap, env, fs, io, path::Path};
 
 use crate::{len_label, tag_set_wrap};
-use angora_common::{cond_stmt_base::CondStmtBase, config, defs, log_data::LogData};
+use angora_common::{
+    cond_stmt_base::CondStmtBase,
+    config, defs,
+    log_data::{LenFieldLog, LogData},
+};
 
 #[derive(Debug)]
 pub struct Logger {
@@ -63,6 +67,7 @@ impl Logger {
 
         let mut order = 0;
 
+        let len_on_lb1 = len_label::is_len_label(cond.lb1);
         // also modify cond to remove len_label information
         let len_cond = len_label::get_len_cond(&mut cond);
 
@@ -75,12 +80,31 @@ impl Logger {
             self.data.cond_list.push(cond);
 
             if let Some(mut c) = len_cond {
+                self.save_len_field(&cond, &c, len_on_lb1);
                 c.order = 0x10000 + order; // avoid the same as cond;
                 self.data.cond_list.push(c);
             }
         }
     }
 
+    // The other operand of the len cond comes from the input, it may be a length field.
+    fn save_len_field(&mut self, cond: &CondStmtBase, len_cond: &CondStmtBase, len_on_lb1: bool) {
+        let (lb, val) = if len_on_lb1 {
+            (cond.lb2, cond.arg2)
+        } else {
+            (cond.lb1, cond.arg1)
+        };
+        if lb == 0 || len_label::is_len_label(lb) {
+            return;
+        }
+        self.data.len_fields.push(LenFieldLog {
+            lb,
+            val,
+            read_offset: len_cond.lb1,
+            read_unit: len_cond.lb2,
+        });
+    }
+
     fn fini(&self) {
         if let Some(fd) = &self.fd {
             let mut writer = io::BufWriter::new(fd);
@@ -3,7 +3,13 @@ use crate::tag_set_wrap;
 use angora_common::{cond_stmt_base::*, defs};
 use lazy_static::lazy_static;
 use libc;
-use std::{slice, sync::Mutex};
+use std::{
+    slice,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
 
 // use shm_conds;
 lazy_static! {
@@ -217,7 +223,9 @@ pub extern "C" fn __angora_trace_exploit_val_tt(
     _b: u32,
     _c: u32,
     _d: u32,
-    _e: u64
+    _e: u64,
+    _f: u64,
+    _g: u32,
 ) {
     panic!("Forbid calling __angora_trace_exploit_val_tt directly");
 }
@@ -229,18 +237,35 @@ pub extern "C" fn __dfsw___angora_trace_exploit_val_tt(
     size: u32,
     op: u32,
     val: u64,
+    ptr: u64,
+    elem_size: u32,
     _l0: DfsanLabel,
     _l1: DfsanLabel,
     _l2: DfsanLabel,
     _l3: DfsanLabel,
     l4: DfsanLabel,
+    _l5: DfsanLabel,
+    _l6: DfsanLabel,
 ) {
     let lb: DfsanLabel = l4;
     if len_label::is_len_label(lb) || lb == 0 {
         return;
     }
 
-    log_cmp(cmpid, context, defs::COND_FALSE_ST, op, size, lb, 0, val, 0);
+    // arg2 is the number of elements from `ptr` to the end of its allocation,
+    // 0 if unknown.
+    let bound = get_exploit_bound(ptr, elem_size);
+    log_cmp(cmpid, context, defs::COND_FALSE_ST, op, size, lb, 0, val, bound);
+}
+
+fn get_exploit_bound(ptr: u64, elem_size: u32) -> u64 {
+    if ptr == 0 || elem_size == 0 {
+        return 0;
+    }
+    match heapmap::heapmap_find(ptr as usize) {
+        Some((base, bound)) => ((base + bound - ptr as usize) / elem_size as usize) as u64,
+        None => 0,
+    }
 }
 
 #[inline]
@@ -280,3 +305,22 @@ pub extern "C" fn __angora_track_fini_rs() {
     let mut lcl = LC.lock().expect("Could not lock LC.");
     *lcl = None;
 }
+
+// No fork server in the track program.
+#[no_mangle]
+pub extern "C" fn __angora_manual_init() {}
+
+// The track program always reads the input from the file.
+#[no_mangle]
+pub extern "C" fn __angora_shm_input(_len: *mut u32) -> *const u8 {
+    std::ptr::null()
+}
+
+static LOOP_FIRST_PASS: AtomicBool = AtomicBool::new(true);
+
+// The conditions are tracked for one input, so the persistent loop
+// of the program runs only once here.
+#[no_mangle]
+pub extern "C" fn __angora_persistent_loop(_max_cnt: u32) -> i32 {
+    LOOP_FIRST_PASS.swap(false, Ordering::SeqCst) as i32
+}
@@ -0,0 +1,78 @@
+// corresponding to common/src/arg_input.rs
+// The children of the fork server replace their arguments and environment
+// variables with the ones the fuzzer takes from the input, before main.
+use angora_common::{arg_input::ArgInput, defs};
+use libc;
+use std::{
+    env,
+    ffi::CString,
+    path::Path,
+    ptr,
+    sync::atomic::{AtomicIsize, AtomicPtr, Ordering},
+};
+
+// More are not searched for argc on the stack.
+const MAX_ARGC: isize = 1 << 16;
+
+static ARGC: AtomicIsize = AtomicIsize::new(0);
+static ARGV: AtomicPtr<*mut libc::c_char> = AtomicPtr::new(ptr::null_mut());
+
+// The argv main will get, found from `environ` in the constructor, before
+// anything sets an environment variable. On the initial stack of the process,
+// argc is followed by the argv pointers, a NULL, and then the envp pointers.
+pub fn save() {
+    extern "C" {
+        static environ: *mut *mut libc::c_char;
+    }
+    let envp = unsafe { environ };
+    if envp.is_null() {
+        return;
+    }
+    unsafe {
+        // envp[-1] is the NULL at the end of argv.
+        if !(*envp.offset(-1)).is_null() {
+            return;
+        }
+        // argv[argc - 1 - k] is at envp[-2 - k], until argc itself.
+        for k in 0..MAX_ARGC {
+            let p = envp.offset(-2 - k);
+            if *p as isize == k {
+                ARGC.store(k, Ordering::SeqCst);
+                ARGV.store(p.offset(1), Ordering::SeqCst);
+                return;
+            }
+        }
+    }
+}
+
+// The last arguments are replaced, since the fuzzer appends them to the fixed ones.
+pub fn reset_arg_input() {
+    let path = match env::var(defs::ARG_INPUT_FILE_VAR) {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+    let input = match ArgInput::read(Path::new(&path)) {
+        Some(i) => i,
+        None => return,
+    };
+    let argc = ARGC.load(Ordering::SeqCst);
+    let argv = ARGV.load(Ordering::SeqCst);
+    if !argv.is_null() && argc as usize > input.args.len() {
+        let first = argc as usize - input.args.len();
+        for (i, a) in input.args.into_iter().enumerate() {
+            // They live until the process exits.
+            if let Ok(a) = CString::new(a) {
+                unsafe {
+                    *argv.add(first + i) = a.into_raw();
+                }
+            }
+        }
+    }
+    for (k, v) in input.envs {
+        if let (Ok(k), Ok(v)) = (CString::new(k), CString::new(v)) {
+            unsafe {
+                libc::setenv(k.as_ptr(), v.as_ptr(), 1);
+            }
+        }
+    }
+}
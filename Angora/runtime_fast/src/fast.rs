@@ -1,18 +1,35 @@
-use super::{shm_conds, forkcli, shm_branches};
-use std::ops::DerefMut;
+use super::{argv, shm_conds, shm_input, forkcli, shm_branches};
+use angora_common::defs;
+use std::{env, ops::DerefMut};
 
 use std::sync::Once;
 
 static START: Once = Once::new();
 
-#[ctor]
-fn fast_init() {
+fn init() {
     START.call_once(|| {
         shm_branches::map_branch_counting_shm();
         forkcli::start_forkcli();
     });
 }
 
+#[ctor]
+fn fast_init() {
+    argv::save();
+    shm_input::init_shm_input();
+    // The program calls __angora_manual_init() by itself.
+    if env::var(defs::DEFER_ENV_VAR).is_err() {
+        init();
+    }
+}
+
+// Start the fork server after the expensive initialization of the program,
+// which does not depend on the input.
+#[no_mangle]
+pub extern "C" fn __angora_manual_init() {
+    init();
+}
+
 #[no_mangle]
 pub extern "C" fn __angora_trace_cmp(
     condition: u32,
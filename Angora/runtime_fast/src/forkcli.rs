@@ -27,17 +27,39 @@ pub fn start_forkcli() {
             let mut sig_buf = [0; 4];
             super::shm_conds::reset_shm_conds();
 
+            let is_persistent = env::var(defs::PERSIST_ENV_VAR).is_ok();
+            if is_persistent {
+                super::persistent::enable();
+            }
+            // A child stopped by itself in persistent mode.
+            let mut child_stopped = false;
+            let mut child_pid = 0;
+
             loop {
                 if socket.read(&mut sig_buf).is_err() {
                     eprintln!("exit forkcli");
+                    if child_stopped {
+                        unsafe {
+                            libc::kill(child_pid, libc::SIGKILL);
+                        }
+                    }
                     process::exit(0);
                 }
 
-                let child_pid = unsafe { libc::fork() };
+                if child_stopped {
+                    child_stopped = false;
+                    unsafe {
+                        libc::kill(child_pid, libc::SIGCONT);
+                    }
+                } else {
+                    child_pid = unsafe { libc::fork() };
 
-                if child_pid == 0 {
-                    super::shm_conds::reset_shm_conds();
-                    return;
+                    if child_pid == 0 {
+                        super::shm_conds::reset_shm_conds();
+                        super::shm_input::reset_shm_input();
+                        super::argv::reset_arg_input();
+                        return;
+                    }
                 }
 
                 let mut pid_buf = vec![];
@@ -49,9 +71,15 @@ pub fn start_forkcli() {
                 }
 
                 let mut status: libc::c_int = 0;
-                if unsafe { libc::waitpid(child_pid, &mut status as *mut libc::c_int, 0) } < 0 {
+                let options = if is_persistent { libc::WUNTRACED } else { 0 };
+                if unsafe { libc::waitpid(child_pid, &mut status as *mut libc::c_int, options) } < 0 {
                     process::exit(1);
                 }
+                // The iteration is finished, and the fuzzer sees it as a normal exit.
+                if libc::WIFSTOPPED(status) {
+                    child_stopped = true;
+                    status = 0;
+                }
 
                 let mut status_buf = vec![];
                 status_buf
@@ -1,6 +1,9 @@
+pub mod argv;
 pub mod fast;
 pub mod forkcli;
+pub mod persistent;
 pub mod shm_conds;
+pub mod shm_input;
 
 mod context;
 mod shm_branches;
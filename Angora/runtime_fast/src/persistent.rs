@@ -0,0 +1,43 @@
+// Persistent mode: run several inputs in one child of the fork server.
+// The target calls it as `while (__ANGORA_LOOP(1000)) { read the input; ... }`.
+// The child stops itself after each iteration, and forkcli resumes it
+// for the next input instead of forking a new one.
+use super::{argv, shm_conds, shm_input};
+use libc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+static IS_PERSISTENT: AtomicBool = AtomicBool::new(false);
+static FIRST_PASS: AtomicBool = AtomicBool::new(true);
+static CYCLE_CNT: AtomicU32 = AtomicU32::new(0);
+
+// Called by forkcli if the fuzzer runs it in persistent mode.
+pub fn enable() {
+    IS_PERSISTENT.store(true, Ordering::SeqCst);
+}
+
+#[no_mangle]
+pub extern "C" fn __angora_persistent_loop(max_cnt: u32) -> i32 {
+    if FIRST_PASS.swap(false, Ordering::SeqCst) {
+        CYCLE_CNT.store(max_cnt, Ordering::SeqCst);
+        return 1;
+    }
+
+    // Without the fork server, it runs only once.
+    if IS_PERSISTENT.load(Ordering::SeqCst) {
+        let cnt = CYCLE_CNT.load(Ordering::SeqCst);
+        if cnt > 1 {
+            CYCLE_CNT.store(cnt - 1, Ordering::SeqCst);
+            unsafe {
+                libc::raise(libc::SIGSTOP);
+            }
+            // The fuzzer has written the next input, condition and cleared
+            // the branches, so reset what we have in this process.
+            shm_conds::reset_shm_conds();
+            shm_input::reset_shm_input();
+            // e.g. the program reads the environment variables in the loop.
+            argv::reset_arg_input();
+            return 1;
+        }
+    }
+    0
+}
@@ -0,0 +1,83 @@
+// corresponding to common/src/shm_input.rs
+// The program gets the input from shared memory with __angora_shm_input,
+// or by reading stdin if it is a stdin target linked with shm_read.c.
+// No lock here: read(2) is called everywhere, also in the child right after fork.
+
+use angora_common::{defs, shm, shm_input::ShmInput};
+use std::{
+    cmp, env, mem, ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+
+// Set once at the start if the fuzzer passes the input in shared memory.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static INPUT: AtomicPtr<ShmInput> = AtomicPtr::new(ptr::null_mut());
+// how many bytes have been read from stdin
+static POS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn init_shm_input() {
+    let shm_id = match env::var(defs::SHM_INPUT_ENV_VAR) {
+        Ok(id) => id.parse::<i32>().expect("Could not parse i32 value."),
+        Err(_) => return,
+    };
+    let input = shm::SHM::<ShmInput>::from_id(shm_id);
+    if input.is_fail() {
+        eprintln!("fail to load input shm");
+        return;
+    }
+    INPUT.store(input.get_ptr(), Ordering::Relaxed);
+    // It is attached until the end, and only the fuzzer removes it.
+    mem::forget(input);
+    ENABLED.store(true, Ordering::Release);
+}
+
+// None if the input is in the file.
+fn get_input() -> Option<&'static [u8]> {
+    if !ENABLED.load(Ordering::Acquire) {
+        return None;
+    }
+    unsafe { INPUT.load(Ordering::Relaxed).as_ref() }.and_then(|s| s.get())
+}
+
+// Before each execution, in the forked child or the next persistent iteration.
+pub fn reset_shm_input() {
+    POS.store(0, Ordering::Relaxed);
+}
+
+// Return the input and set its length, or NULL if the program should
+// read it from the file (or stdin) as usual.
+#[no_mangle]
+pub extern "C" fn __angora_shm_input(len: *mut u32) -> *const u8 {
+    match get_input() {
+        Some(input) => {
+            if !len.is_null() {
+                unsafe {
+                    *len = input.len() as u32;
+                }
+            }
+            input.as_ptr()
+        },
+        None => ptr::null(),
+    }
+}
+
+// For the read(2) of stdin in llvm_mode/external_lib/shm_read.c, which is only linked
+// with ANGORA_SHM_READ=1. Return -1 if the program should read the file as usual.
+#[no_mangle]
+pub extern "C" fn __angora_shm_input_read(buf: *mut u8, count: usize) -> isize {
+    if buf.is_null() {
+        return -1;
+    }
+    match get_input() {
+        Some(input) => {
+            let pos = cmp::min(POS.load(Ordering::Relaxed), input.len());
+            let n = cmp::min(input.len() - pos, count);
+            unsafe {
+                ptr::copy_nonoverlapping(input[pos..].as_ptr(), buf, n);
+            }
+            POS.store(pos + n, Ordering::Relaxed);
+            n as isize
+        },
+        None => -1,
+    }
+}